@@ -0,0 +1,52 @@
+const MODULO: u32 = 65521;
+
+/// The largest number of bytes that can be summed before the sums must be reduced.
+const BLOCK_SIZE: usize = 5552;
+
+/// Computes the Adler-32 checksum for the given data slice.
+///
+/// # Arguments
+/// * `data` - A byte slice to compute the checksum for.
+///
+/// # Returns
+/// * `u32` - The Adler-32 checksum.
+#[inline]
+pub fn compute(data: &[u8]) -> u32 {
+    return update(1, data);
+}
+
+/// Updates a running Adler-32 checksum with the given data slice.
+///
+/// # Arguments
+/// * `adler` - The checksum computed so far, `1` for an empty input.
+/// * `data` - A byte slice to append to the checksum.
+///
+/// # Returns
+/// * `u32` - The updated Adler-32 checksum.
+pub fn update(adler: u32, data: &[u8]) -> u32 {
+    let mut a = adler & 0xffffu32;
+    let mut b = adler >> 16u32;
+
+    for block in data.chunks(BLOCK_SIZE) {
+        for &byte in block {
+            a += byte as u32;
+            b += a;
+        }
+
+        a %= MODULO;
+        b %= MODULO;
+    }
+
+    return (b << 16u32) | a;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adler32_known_value() {
+        let data = b"Wikipedia";
+        assert_eq!(compute(data), 0x11e60398);
+    }
+}
@@ -0,0 +1,361 @@
+use crate::convert;
+use crate::error::ParserError;
+use crate::filter;
+use crate::image::ImageLayout;
+use crate::inflate;
+use crate::spec::*;
+
+/// Represents a single frame of an animation with its compressed data.
+#[derive(Debug, Clone, Copy)]
+pub struct Frame<'a> {
+    /// The frame control.
+    pub control: &'a FrameControlInfo,
+    /// The compressed frame data, in the same format as the `IDAT` data.
+    pub data: &'a [u8],
+    /// Whether the frame data is the default image stored in `IDAT` chunks.
+    pub is_default_image: bool,
+}
+
+/// Represents the frames of an animated image.
+#[derive(Debug, Clone)]
+pub struct Frames<'a> {
+    /// The animation control.
+    pub animation_control: &'a AnimationControlInfo,
+    /// Whether the default image is the first frame of the animation, otherwise it is only shown by decoders without animation support.
+    pub is_default_image_animated: bool,
+    /// The frames in the order they are rendered.
+    pub frames: Vec<Frame<'a>>,
+}
+
+/// Represents a fully composited frame of an animation.
+#[derive(Debug, Clone)]
+pub struct RenderedFrame {
+    /// The width of the canvas.
+    pub width: u32,
+    /// The height of the canvas.
+    pub height: u32,
+    /// The 8-bit RGBA samples of the whole canvas.
+    pub pixels: Vec<u8>,
+    /// The time to show the frame in seconds.
+    pub delay: f64,
+}
+
+impl PngInfo {
+    /// Returns whether the image has an animation control.
+    pub fn is_animated(&self) -> bool {
+        return self.animation_control.is_some();
+    }
+
+    /// Returns the frames of the animation, or `None` if the image is not animated.
+    pub fn frames(&self) -> Option<Frames<'_>> {
+        let animation_control = self.animation_control.as_ref()?;
+
+        let frames: Vec<Frame<'_>> = self
+            .animation_frames
+            .iter()
+            .map(|frame| match &frame.data {
+                Some(frame_data) => Frame {
+                    control: &frame.control,
+                    data: &frame_data.data,
+                    is_default_image: false,
+                },
+                None => Frame {
+                    control: &frame.control,
+                    data: &self.compressed_data.data,
+                    is_default_image: true,
+                },
+            })
+            .collect();
+
+        return Some(Frames {
+            animation_control,
+            is_default_image_animated: frames.first().is_some_and(|frame| frame.is_default_image),
+            frames,
+        });
+    }
+
+    /// Renders every frame of the animation onto the canvas, applying the dispose and blend operations.
+    ///
+    /// A static image is rendered as a single frame without delay.
+    ///
+    /// # Returns
+    /// * `Vec<RenderedFrame>` - The composited canvas after each frame is rendered.
+    pub fn render_frames(&self) -> Result<Vec<RenderedFrame>, ParserError> {
+        let (width, height) = (self.header.width, self.header.height);
+
+        let Some(frames) = self.frames() else {
+            let image = self.decode()?;
+            return Ok(vec![RenderedFrame {
+                width,
+                height,
+                pixels: image.as_rgba8(),
+                delay: 0.0,
+            }]);
+        };
+
+        let mut canvas = vec![0u8; width as usize * height as usize * 4];
+        let mut rendered = Vec::with_capacity(frames.frames.len());
+
+        for (index, frame) in frames.frames.iter().enumerate() {
+            let control = frame.control;
+            let pixels = self.decode_frame(control, frame.data)?;
+
+            // The first frame has no previous contents, so it is cleared instead.
+            let dispose_op = match (index, control.dispose_op) {
+                (0, DisposeOp::Previous) => DisposeOp::Background,
+                (_, dispose_op) => dispose_op,
+            };
+
+            let previous = match dispose_op {
+                DisposeOp::Previous => Some(canvas.clone()),
+                _ => None,
+            };
+
+            for_each_pixel(control, width, |source, target| {
+                let pixel = &pixels[source..source + 4];
+                match control.blend_op {
+                    BlendOp::Source => canvas[target..target + 4].copy_from_slice(pixel),
+                    BlendOp::Over => blend_over(pixel, &mut canvas[target..target + 4]),
+                }
+            });
+
+            rendered.push(RenderedFrame {
+                width,
+                height,
+                pixels: canvas.clone(),
+                delay: control.delay(),
+            });
+
+            match (dispose_op, previous) {
+                (DisposeOp::Background, _) => for_each_pixel(control, width, |_, target| {
+                    canvas[target..target + 4].fill(0);
+                }),
+                (DisposeOp::Previous, Some(previous)) => canvas = previous,
+                _ => {}
+            }
+        }
+
+        return Ok(rendered);
+    }
+
+    /// Decompresses and unfilters the data of a frame into 8-bit RGBA samples.
+    fn decode_frame(
+        &self,
+        control: &FrameControlInfo,
+        data: &[u8],
+    ) -> Result<Vec<u8>, ParserError> {
+        let header = HeaderInfo {
+            width: control.width,
+            height: control.height,
+            ..self.header.clone()
+        };
+
+        let layout = ImageLayout::from_header(&header);
+        let limit = filter::image_data_size(&header).ok_or(ParserError::InvalidImageDataLength)?;
+
+        // The frames of Apple optimized images are stored like their default image.
+        let pixels = match self.cgbi {
+            Some(_) => {
+                let data = inflate::decompress_raw_with_limit(data, limit)?;
+                let mut pixels = filter::unfilter(&header, &data)?;
+                convert::restore_cgbi_samples(&layout, &mut pixels);
+                pixels
+            }
+            None => filter::unfilter(&header, &inflate::decompress_with_limit(data, limit)?)?,
+        };
+
+        return Ok(convert::to_rgba8(
+            &layout,
+            &pixels,
+            self.palette
+                .as_ref()
+                .map(|palette| palette.entries.as_slice())
+                .unwrap_or(&[]),
+            self.transparency
+                .as_ref()
+                .map(|transparency| &transparency.transparency),
+        ));
+    }
+}
+
+/// Calls the provided function with the offsets of each pixel of the frame and the canvas pixel it covers.
+fn for_each_pixel<F: FnMut(usize, usize)>(control: &FrameControlInfo, canvas_width: u32, mut f: F) {
+    for y in 0..control.height as usize {
+        for x in 0..control.width as usize {
+            let source = (y * control.width as usize + x) * 4;
+            let target = ((control.y_offset as usize + y) * canvas_width as usize
+                + control.x_offset as usize
+                + x)
+                * 4;
+            f(source, target);
+        }
+    }
+}
+
+/// Composites the source pixel over the target pixel using non-premultiplied alpha.
+fn blend_over(source: &[u8], target: &mut [u8]) {
+    let source_alpha = source[3] as u32;
+    if source_alpha == 255 {
+        target.copy_from_slice(source);
+        return;
+    }
+
+    if source_alpha == 0 {
+        return;
+    }
+
+    let target_alpha = target[3] as u32 * (255 - source_alpha) / 255;
+    let alpha = source_alpha + target_alpha;
+
+    for channel in 0..3 {
+        let color = source[channel] as u32 * source_alpha + target[channel] as u32 * target_alpha;
+        target[channel] = ((color + alpha / 2) / alpha) as u8;
+    }
+
+    target[3] = alpha as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::deflate::{self, CompressionLevel};
+    use crate::error::ParserError;
+    use crate::parser::Parser;
+    use crate::test_utils::chunk;
+    use crate::writer::Writer;
+
+    fn frame_control(
+        sequence_number: u32,
+        size: u32,
+        offset: u32,
+        dispose_op: DisposeOp,
+    ) -> Vec<u8> {
+        return [
+            sequence_number.to_be_bytes(),
+            size.to_be_bytes(),
+            size.to_be_bytes(),
+            offset.to_be_bytes(),
+            offset.to_be_bytes(),
+        ]
+        .concat()
+        .into_iter()
+        .chain([0, 1, 0, 10, dispose_op as u8, 1])
+        .collect();
+    }
+
+    /// Builds a 2x2 grayscale APNG whose default image is the first frame, followed by a 1x1 frame, both disposed
+    /// with the provided operation.
+    fn animated_png(second_sequence_number: u32, dispose_op: DisposeOp) -> Vec<u8> {
+        let image_data = deflate::compress(&[0, 10, 20, 0, 30, 40], CompressionLevel::Default);
+        let frame_data = deflate::compress(&[0, 50], CompressionLevel::Default);
+        let fdat = [&second_sequence_number.to_be_bytes()[..], &frame_data].concat();
+
+        return [
+            SIGNATURE.to_vec(),
+            chunk(b"IHDR", &[0, 0, 0, 2, 0, 0, 0, 2, 8, 0, 0, 0, 0]),
+            chunk(b"acTL", &[0, 0, 0, 2, 0, 0, 0, 0]),
+            chunk(b"fcTL", &frame_control(0, 2, 0, dispose_op)),
+            chunk(b"IDAT", &image_data),
+            chunk(b"fcTL", &frame_control(1, 1, 1, dispose_op)),
+            chunk(b"fdAT", &fdat),
+            chunk(b"IEND", &[]),
+        ]
+        .concat();
+    }
+
+    #[test]
+    fn test_parse_frames() {
+        let info = Parser::parse(&mut Cursor::new(animated_png(2, DisposeOp::Background))).unwrap();
+        let frames = info.frames().unwrap();
+
+        assert!(frames.is_default_image_animated);
+        assert_eq!(frames.frames.len(), 2);
+        assert!(frames.frames[0].is_default_image);
+        assert_eq!(frames.frames[1].control.x_offset, 1);
+        assert_eq!(frames.frames[1].control.delay(), 0.1);
+        assert_eq!(frames.frames[1].control.blend_op, BlendOp::Over);
+        assert_eq!(inflate::decompress(frames.frames[1].data).unwrap(), [0, 50]);
+
+        let mut output = Vec::new();
+        Writer::write(&mut output, &info).unwrap();
+        assert_eq!(output, animated_png(2, DisposeOp::Background));
+    }
+
+    #[test]
+    fn test_render_frames() {
+        let info = Parser::parse(&mut Cursor::new(animated_png(2, DisposeOp::None))).unwrap();
+        let frames = info.render_frames().unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].delay, 0.1);
+        assert_eq!(
+            frames[0].pixels,
+            [
+                10, 10, 10, 255, 20, 20, 20, 255, 30, 30, 30, 255, 40, 40, 40, 255
+            ]
+        );
+        // The second frame is blended over the bottom-right pixel of the first one.
+        assert_eq!(frames[1].pixels[..12], frames[0].pixels[..12]);
+        assert_eq!(frames[1].pixels[12..], [50, 50, 50, 255]);
+
+        // The first frame of the other image is cleared before the second one is rendered.
+        let info = Parser::parse(&mut Cursor::new(animated_png(2, DisposeOp::Background))).unwrap();
+        let frames = info.render_frames().unwrap();
+        assert!(frames[1].pixels[..12].iter().all(|&value| value == 0));
+        assert_eq!(frames[1].pixels[12..], [50, 50, 50, 255]);
+    }
+
+    #[test]
+    fn test_render_cgbi_frames() {
+        // A 2x1 BGRA image with premultiplied alpha and raw deflate image data, which is its only frame.
+        let scanline = [0, 30, 20, 10, 255, 0, 32, 64, 64];
+        let png = [
+            SIGNATURE.to_vec(),
+            chunk(b"CgBI", &[0x50, 0x00, 0x20, 0x06]),
+            chunk(b"IHDR", &[0, 0, 0, 2, 0, 0, 0, 1, 8, 6, 0, 0, 0]),
+            chunk(b"acTL", &[0, 0, 0, 1, 0, 0, 0, 0]),
+            chunk(
+                b"fcTL",
+                &[
+                    &0u32.to_be_bytes()[..],
+                    &2u32.to_be_bytes(),
+                    &1u32.to_be_bytes(),
+                    &[0; 8],
+                    &[0, 1, 0, 10, 0, 0],
+                ]
+                .concat(),
+            ),
+            chunk(
+                b"IDAT",
+                &deflate::compress_raw(&scanline, CompressionLevel::Default),
+            ),
+            chunk(b"IEND", &[]),
+        ]
+        .concat();
+
+        let info = Parser::parse(&mut Cursor::new(&png)).unwrap();
+        let frames = info.render_frames().unwrap();
+        assert_eq!(frames[0].pixels, [10, 20, 30, 255, 255, 128, 0, 64]);
+    }
+
+    #[test]
+    fn test_blend_over() {
+        let mut target = [0, 0, 255, 255];
+        blend_over(&[255, 0, 0, 128], &mut target);
+        assert_eq!(target, [128, 0, 127, 255]);
+
+        let mut target = [0, 0, 0, 0];
+        blend_over(&[255, 0, 0, 128], &mut target);
+        assert_eq!(target, [255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn test_invalid_sequence_number() {
+        assert!(matches!(
+            Parser::parse(&mut Cursor::new(animated_png(3, DisposeOp::Background))),
+            Err(ParserError::InvalidSequenceNumber(chunk_ids::fdAT))
+        ));
+    }
+}
@@ -0,0 +1,319 @@
+use std::future::poll_fn;
+use std::io::{self, ErrorKind};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::error::ParserError;
+use crate::parser::ParserOptions;
+use crate::spec::*;
+use crate::stream::{DecodeEvent, StreamDecoder};
+
+/// Describes a source of bytes that is read asynchronously.
+///
+/// The trait has the same shape as the `AsyncRead` traits of the common async runtimes, so a source of any runtime
+/// can be used through a small wrapper that forwards `poll_read`.
+///
+/// A wrapper for sources implementing `futures_io::AsyncRead`, such as those of `async-std` and `smol`:
+///
+/// ```ignore
+/// struct FuturesSource<Source>(Source);
+///
+/// impl<Source: futures_io::AsyncRead + Unpin> png_rs::async_parser::AsyncRead for FuturesSource<Source> {
+///     fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+///         return Pin::new(&mut self.0).poll_read(cx, buf);
+///     }
+/// }
+/// ```
+///
+/// A wrapper for sources implementing `tokio::io::AsyncRead`, which reads into a `ReadBuf` instead of a slice:
+///
+/// ```ignore
+/// struct TokioSource<Source>(Source);
+///
+/// impl<Source: tokio::io::AsyncRead + Unpin> png_rs::async_parser::AsyncRead for TokioSource<Source> {
+///     fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+///         let mut buf = tokio::io::ReadBuf::new(buf);
+///         return match Pin::new(&mut self.0).poll_read(cx, &mut buf) {
+///             Poll::Ready(Ok(())) => Poll::Ready(Ok(buf.filled().len())),
+///             Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+///             Poll::Pending => Poll::Pending,
+///         };
+///     }
+/// }
+/// ```
+pub trait AsyncRead {
+    /// Attempts to read data into the provided buffer.
+    ///
+    /// # Arguments
+    /// * `cx` - The context of the task, whose waker is woken once data may be available again.
+    /// * `buf` - The buffer to read the data into.
+    ///
+    /// # Returns
+    /// * `Poll<io::Result<usize>>` - The number of bytes read, which is 0 at the end of the source, or `Poll::Pending`
+    ///   if no data is available yet.
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>;
+}
+
+impl AsyncRead for &[u8] {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let count = buf.len().min(self.len());
+        let (data, rest) = self.split_at(count);
+        buf[..count].copy_from_slice(data);
+        *self = rest;
+
+        return Poll::Ready(Ok(count));
+    }
+}
+
+impl<Source: AsyncRead + Unpin + ?Sized> AsyncRead for &mut Source {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        return Pin::new(&mut **self).poll_read(cx, buf);
+    }
+}
+
+impl<Source: AsyncRead + Unpin + ?Sized> AsyncRead for Box<Source> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        return Pin::new(&mut **self).poll_read(cx, buf);
+    }
+}
+
+/// Represents a parser that handles PNG data read from an asynchronous source.
+///
+/// Chunks are validated exactly as by `Parser`, and the parser does not depend on any particular runtime.
+pub struct AsyncParser<'a, Source: AsyncRead + Unpin> {
+    source: &'a mut Source,
+    decoder: StreamDecoder<'a>,
+}
+
+impl<'a, Source: AsyncRead + Unpin> AsyncParser<'a, Source> {
+    /// Tries to parse PNG data from the provided asynchronous source.
+    pub async fn parse(source: &'a mut Source) -> Result<PngInfo, ParserError> {
+        let parser = Self {
+            source,
+            decoder: StreamDecoder::without_rows(None),
+        };
+
+        return parser.parse_chunks().await;
+    }
+
+    /// Tries to parse PNG data from the provided asynchronous source with the provided options.
+    ///
+    /// # Arguments
+    /// * `source` - The source to read the PNG data from.
+    /// * `options` - The options that customize the parsing.
+    ///
+    /// # Returns
+    /// * `Result<PngInfo, ParserError>` - The parsed info, or the error that stopped the parsing.
+    pub async fn parse_with_options(
+        source: &'a mut Source,
+        options: &'a ParserOptions,
+    ) -> Result<PngInfo, ParserError> {
+        let parser = Self {
+            source,
+            decoder: StreamDecoder::without_rows(Some(options)),
+        };
+
+        return parser.parse_chunks().await;
+    }
+
+    /// Feeds the data of the source to the decoder in blocks until the trailer has been read.
+    async fn parse_chunks(mut self) -> Result<PngInfo, ParserError> {
+        let mut buffer = [0u8; 8192];
+
+        loop {
+            match self.decoder.next_event()? {
+                DecodeEvent::End(info) => return Ok(*info),
+                DecodeEvent::NeedMoreData => {
+                    let count = read(self.source, &mut buffer).await?;
+                    if count == 0 {
+                        return self.decoder.finish();
+                    }
+
+                    self.decoder.feed(&buffer[..count]);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Reads the next block of data, and returns the number of bytes read, which is 0 at the end of the source.
+async fn read<Source: AsyncRead + Unpin>(
+    source: &mut Source,
+    buffer: &mut [u8],
+) -> Result<usize, ParserError> {
+    loop {
+        match poll_fn(|cx| Pin::new(&mut *source).poll_read(cx, buffer)).await {
+            Ok(count) => return Ok(count),
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(ParserError::IOError(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::io::Cursor;
+    use std::task::Waker;
+
+    use super::*;
+    use crate::encoder::{self, EncoderOptions};
+    use crate::parser::{ImageDataHandling, Parser};
+
+    /// Represents an in-memory source that returns at most 3 bytes per read, and is not ready on every other read.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        ready: bool,
+    }
+
+    impl AsyncRead for Trickle<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            let count = buf.len().min(self.data.len()).min(3);
+            buf[..count].copy_from_slice(&self.data[..count]);
+            self.data = &self.data[count..];
+
+            return Poll::Ready(Ok(count));
+        }
+    }
+
+    /// Polls the future until it completes, which works for sources that wake the task before returning pending.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    fn png() -> Vec<u8> {
+        let header = HeaderInfo {
+            width: 9,
+            height: 7,
+            bit_depth: 8,
+            color_type: ColorType::TrueColorAlpha,
+            compression_method: CompressionMethod::Deflate,
+            filter_method: FilterMethod::Adaptive,
+            interlace_method: InterlaceMethod::None,
+        };
+        let pixels: Vec<u8> = (0..header.stride() * 7)
+            .map(|i| (i * 5 % 253) as u8)
+            .collect();
+        let options = EncoderOptions {
+            max_chunk_size: 32,
+            ..Default::default()
+        };
+
+        let mut png = Vec::new();
+        encoder::encode(&mut png, header, None, &pixels, &options).unwrap();
+        return png;
+    }
+
+    #[test]
+    fn test_parse_async() {
+        let png = png();
+        let expected = Parser::parse(&mut Cursor::new(&png)).unwrap();
+
+        let mut source = Trickle {
+            data: &png,
+            ready: false,
+        };
+        let info = block_on(AsyncParser::parse(&mut source)).unwrap();
+
+        assert_eq!(info.compressed_data.data, expected.compressed_data.data);
+        assert_eq!(
+            info.compressed_data.chunk_count,
+            expected.compressed_data.chunk_count
+        );
+        assert_eq!(
+            info.decode().unwrap().pixels,
+            expected.decode().unwrap().pixels
+        );
+
+        let options = ParserOptions::new().with_image_data_handling(ImageDataHandling::Verify);
+        let mut source = &png[..];
+        let info = block_on(AsyncParser::parse_with_options(&mut source, &options)).unwrap();
+        let mut cursor = Cursor::new(&png);
+        let expected = Parser::parse_with_options(&mut cursor, &options).unwrap();
+        assert!(info.compressed_data.data.is_empty());
+        assert_eq!(info.image_data_spans, expected.image_data_spans);
+    }
+
+    #[test]
+    fn test_parse_async_errors() {
+        let mut png = png();
+
+        // The source ends right after the signature.
+        let mut source = &SIGNATURE[..];
+        assert!(matches!(
+            block_on(AsyncParser::parse(&mut source)),
+            Err(ParserError::MissingRequiredChunk(chunk_ids::IHDR))
+        ));
+
+        // The source ends right before the trailer.
+        let mut source = &png[..png.len() - 12];
+        assert!(matches!(
+            block_on(AsyncParser::parse(&mut source)),
+            Err(ParserError::MissingRequiredChunk(chunk_ids::IEND))
+        ));
+
+        let mut source = &png[..png.len() - 6];
+        assert!(matches!(
+            block_on(AsyncParser::parse(&mut source)),
+            Err(ParserError::IOError(_))
+        ));
+
+        // Corrupts the CRC of the first image data chunk, which is not checked when the image data is skipped.
+        assert_eq!(&png[37..41], b"IDAT");
+        let length = u32::from_be_bytes([png[33], png[34], png[35], png[36]]) as usize;
+        png[41 + length] ^= 1;
+
+        let options = ParserOptions::new().with_image_data_handling(ImageDataHandling::Skip);
+        let mut source = &png[..];
+        assert!(block_on(AsyncParser::parse_with_options(&mut source, &options)).is_ok());
+
+        let options = ParserOptions::new().with_image_data_handling(ImageDataHandling::Verify);
+        let mut source = &png[..];
+        assert!(matches!(
+            block_on(AsyncParser::parse_with_options(&mut source, &options)),
+            Err(ParserError::CorruptedData)
+        ));
+
+        // Corrupts the CRC of the header.
+        png[29] ^= 1;
+        let mut source = &png[..];
+        assert!(matches!(
+            block_on(AsyncParser::parse(&mut source)),
+            Err(ParserError::CorruptedData)
+        ));
+    }
+}
@@ -0,0 +1,460 @@
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+
+use crate::crc32;
+use crate::error::ParserError;
+use crate::parser::ImageDataHandling;
+use crate::spec::*;
+use crate::utils;
+
+/// The largest chunk length allowed by the PNG specification.
+pub(crate) const MAX_CHUNK_LENGTH: u32 = i32::MAX as u32;
+
+/// Returns whether the chunk is critical, which is marked by an uppercase first letter.
+pub fn is_critical(chunk_type: ChunkId) -> bool {
+    return chunk_type.to_be_bytes()[0] & 0x20 == 0;
+}
+
+/// Returns whether the chunk is defined by the specification or registered, which is marked by an uppercase second letter.
+pub fn is_public(chunk_type: ChunkId) -> bool {
+    return chunk_type.to_be_bytes()[1] & 0x20 == 0;
+}
+
+/// Returns whether the reserved bit is valid, which is marked by an uppercase third letter.
+pub fn is_reserved_bit_valid(chunk_type: ChunkId) -> bool {
+    return chunk_type.to_be_bytes()[2] & 0x20 == 0;
+}
+
+/// Returns whether the chunk can be copied by editors that do not recognize it even after modifying critical chunks, which is marked by a lowercase fourth letter.
+pub fn is_safe_to_copy(chunk_type: ChunkId) -> bool {
+    return chunk_type.to_be_bytes()[3] & 0x20 != 0;
+}
+
+/// Returns whether every byte of the chunk type is an ASCII letter.
+pub fn is_valid_chunk_type(chunk_type: ChunkId) -> bool {
+    return chunk_type
+        .to_be_bytes()
+        .iter()
+        .all(|byte| byte.is_ascii_alphabetic());
+}
+
+/// Represents a raw chunk read from a PNG data stream.
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    /// The chunk type.
+    pub chunk_type: ChunkId,
+    /// The position of the chunk length field from the start of the data stream.
+    pub offset: u64,
+    /// The length of the chunk data.
+    pub length: u32,
+    /// The chunk data.
+    pub data: Vec<u8>,
+    /// Whether the stored CRC matches the one computed over the chunk type and data.
+    pub crc_ok: bool,
+}
+
+impl Chunk {
+    /// Returns the chunk type as its four letters.
+    pub fn type_name(&self) -> [u8; 4] {
+        return self.chunk_type.to_be_bytes();
+    }
+
+    /// Returns whether the chunk is critical.
+    pub fn is_critical(&self) -> bool {
+        return is_critical(self.chunk_type);
+    }
+
+    /// Returns whether the chunk is ancillary.
+    pub fn is_ancillary(&self) -> bool {
+        return !is_critical(self.chunk_type);
+    }
+
+    /// Returns whether the chunk is public.
+    pub fn is_public(&self) -> bool {
+        return is_public(self.chunk_type);
+    }
+
+    /// Returns whether the chunk is private.
+    pub fn is_private(&self) -> bool {
+        return !is_public(self.chunk_type);
+    }
+
+    /// Returns whether the chunk is safe to copy.
+    pub fn is_safe_to_copy(&self) -> bool {
+        return is_safe_to_copy(self.chunk_type);
+    }
+}
+
+/// Represents an iterator over the raw chunks of a PNG data stream.
+///
+/// Chunks are yielded without any semantic validation, and a CRC mismatch is reported through `Chunk::crc_ok`
+/// instead of failing. The iteration ends after the `IEND` chunk, at the end of the source, or after an error.
+pub struct ChunkReader<'a, Source: Read> {
+    source: &'a mut Source,
+    offset: u64,
+    finished: bool,
+}
+
+impl<'a, Source: Read> ChunkReader<'a, Source> {
+    /// Validates the signature and creates a reader positioned at the first chunk.
+    pub fn new(source: &'a mut Source) -> Result<Self, ParserError> {
+        let mut signature = [0u8; 8];
+        utils::read_to(source, &mut signature)?;
+        if signature != SIGNATURE {
+            return Err(ParserError::InvalidSignature);
+        }
+
+        return Ok(Self {
+            source,
+            offset: SIGNATURE.len() as u64,
+            finished: false,
+        });
+    }
+
+    /// Reads the next chunk like `next`, but handles the data of an `IDAT` chunk as provided.
+    ///
+    /// Unless the data is buffered, the yielded chunk has empty data, and a skipped chunk always reports a valid CRC.
+    pub(crate) fn next_with(
+        &mut self,
+        image_data: ImageDataHandling,
+    ) -> Option<Result<Chunk, ParserError>> {
+        if self.finished {
+            return None;
+        }
+
+        return match self.read_chunk(image_data) {
+            Ok(Some(chunk)) => {
+                self.finished = chunk.chunk_type == chunk_ids::IEND;
+                Some(Ok(chunk))
+            }
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        };
+    }
+
+    /// Reads the next chunk, or returns `None` if the source ends right at a chunk boundary.
+    fn read_chunk(&mut self, image_data: ImageDataHandling) -> Result<Option<Chunk>, ParserError> {
+        let mut length = [0u8; 4];
+        let mut filled = 0;
+        while filled < length.len() {
+            match self.source.read(&mut length[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(ParserError::IOError(ErrorKind::UnexpectedEof.into())),
+                Ok(count) => filled += count,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(ParserError::IOError(e)),
+            }
+        }
+
+        let length = utils::to_u32(&length);
+        let mut chunk_type = [0u8; 4];
+        utils::read_to(self.source, &mut chunk_type)?;
+        let chunk_type = utils::to_u32(&chunk_type);
+
+        if length > MAX_CHUNK_LENGTH {
+            return Err(ParserError::InvalidChunkLength(chunk_type));
+        }
+
+        let handling = match chunk_type {
+            chunk_ids::IDAT => image_data,
+            _ => ImageDataHandling::Buffer,
+        };
+
+        let (data, crc_ok) = match handling {
+            ImageDataHandling::Buffer => {
+                let data = utils::read_bytes(self.source, length as usize)?;
+                let crc = utils::read_u32(self.source)?;
+                let computed_crc = crc32::update(crc32::compute(&chunk_type.to_be_bytes()), &data);
+                (data, computed_crc == crc)
+            }
+            ImageDataHandling::Verify => (Vec::new(), self.verify_data(chunk_type, length)?),
+            ImageDataHandling::Skip => {
+                utils::skip(self.source, length as u64 + 4)?;
+                (Vec::new(), true)
+            }
+        };
+
+        let chunk = Chunk {
+            chunk_type,
+            offset: self.offset,
+            length,
+            data,
+            crc_ok,
+        };
+
+        self.offset += 12 + length as u64;
+
+        return Ok(Some(chunk));
+    }
+
+    /// Reads the chunk data in blocks to check its CRC without buffering it.
+    fn verify_data(&mut self, chunk_type: ChunkId, length: u32) -> Result<bool, ParserError> {
+        let mut buffer = [0u8; 8192];
+        let mut crc = crc32::compute(&chunk_type.to_be_bytes());
+        let mut remaining = length as usize;
+
+        while remaining > 0 {
+            let count = remaining.min(buffer.len());
+            crc = crc32::update(crc, utils::read_to(self.source, &mut buffer[..count])?);
+            remaining -= count;
+        }
+
+        return Ok(utils::read_u32(self.source)? == crc);
+    }
+}
+
+impl<Source: Read> Iterator for ChunkReader<'_, Source> {
+    type Item = Result<Chunk, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        return self.next_with(ImageDataHandling::Buffer);
+    }
+}
+
+/// Represents a reader over the compressed image data that was left in the source while parsing.
+///
+/// The data of each `IDAT` chunk is read in order from its recorded position, which is relative to the start of the
+/// PNG data stream. The position of the stream in the source is the base that the positions are added to.
+pub struct ImageDataReader<'a, Source: Read + Seek> {
+    source: &'a mut Source,
+    base: u64,
+    spans: &'a [DataSpan],
+    remaining: u64,
+}
+
+impl<Source: Read + Seek> Read for ImageDataReader<'_, Source> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while self.remaining == 0 {
+            let Some((span, spans)) = self.spans.split_first() else {
+                return Ok(0);
+            };
+
+            let Some(offset) = self.base.checked_add(span.offset) else {
+                return Err(ErrorKind::InvalidInput.into());
+            };
+
+            self.source.seek(SeekFrom::Start(offset))?;
+            self.remaining = span.length as u64;
+            self.spans = spans;
+        }
+
+        let count = (buf.len() as u64).min(self.remaining) as usize;
+        let count = self.source.read(&mut buf[..count])?;
+        if count == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+
+        self.remaining -= count as u64;
+
+        return Ok(count);
+    }
+}
+
+impl PngInfo {
+    /// Creates a reader that streams the compressed image data recorded in `image_data_spans` from the source.
+    ///
+    /// The PNG data stream must start at the start of the source, see `image_data_reader_at` otherwise.
+    ///
+    /// # Arguments
+    /// * `source` - The source that the image was parsed from.
+    ///
+    /// # Returns
+    /// * `ImageDataReader<Source>` - The reader over the concatenated data of the `IDAT` chunks.
+    pub fn image_data_reader<'a, Source: Read + Seek>(
+        &'a self,
+        source: &'a mut Source,
+    ) -> ImageDataReader<'a, Source> {
+        return self.image_data_reader_at(source, 0);
+    }
+
+    /// Creates a reader that streams the compressed image data recorded in `image_data_spans` from the source, in
+    /// which the PNG data stream starts at the provided position.
+    ///
+    /// # Arguments
+    /// * `source` - The source that the image was parsed from.
+    /// * `base` - The position of the PNG signature in the source.
+    ///
+    /// # Returns
+    /// * `ImageDataReader<Source>` - The reader over the concatenated data of the `IDAT` chunks.
+    pub fn image_data_reader_at<'a, Source: Read + Seek>(
+        &'a self,
+        source: &'a mut Source,
+        base: u64,
+    ) -> ImageDataReader<'a, Source> {
+        return ImageDataReader {
+            source,
+            base,
+            spans: &self.image_data_spans,
+            remaining: 0,
+        };
+    }
+
+    /// Reads the compressed image data recorded in `image_data_spans` into `compressed_data`, which makes the info
+    /// usable for decoding and writing.
+    ///
+    /// The PNG data stream must start at the start of the source, see `load_image_data_at` otherwise.
+    pub fn load_image_data<Source: Read + Seek>(
+        &mut self,
+        source: &mut Source,
+    ) -> Result<(), ParserError> {
+        return self.load_image_data_at(source, 0);
+    }
+
+    /// Reads the compressed image data recorded in `image_data_spans` into `compressed_data` from the source, in
+    /// which the PNG data stream starts at the provided position.
+    ///
+    /// # Arguments
+    /// * `source` - The source that the image was parsed from.
+    /// * `base` - The position of the PNG signature in the source.
+    pub fn load_image_data_at<Source: Read + Seek>(
+        &mut self,
+        source: &mut Source,
+        base: u64,
+    ) -> Result<(), ParserError> {
+        let mut data = Vec::new();
+        if let Err(e) = self
+            .image_data_reader_at(source, base)
+            .read_to_end(&mut data)
+        {
+            return Err(ParserError::IOError(e));
+        }
+
+        self.compressed_data.data = data;
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::parser::{Parser, ParserOptions};
+    use crate::test_utils::{chunk, split_png};
+
+    #[test]
+    fn test_chunk_properties() {
+        assert!(is_critical(chunk_ids::IHDR));
+        assert!(!is_critical(chunk_ids::tEXt));
+        assert!(is_public(chunk_ids::tEXt));
+        assert!(!is_public(u32::from_be_bytes(*b"prVt")));
+        assert!(is_safe_to_copy(chunk_ids::tEXt));
+        assert!(!is_safe_to_copy(chunk_ids::gAMA));
+        assert!(is_reserved_bit_valid(chunk_ids::gAMA));
+        assert!(!is_valid_chunk_type(u32::from_be_bytes(*b"IH1R")));
+    }
+
+    #[test]
+    fn test_read_chunks() {
+        let mut png = SIGNATURE.to_vec();
+        for (chunk_type, data) in [(b"IHDR", &[1u8, 2, 3][..]), (b"abCd", &[]), (b"IEND", &[])] {
+            png.extend(chunk(chunk_type, data));
+        }
+
+        // Corrupting the last byte of the second chunk makes its CRC mismatch.
+        png[8 + 15 + 11] ^= 0xff;
+        png.extend_from_slice(b"trailing data after IEND");
+
+        let mut source = Cursor::new(png);
+        let chunks: Vec<Chunk> = ChunkReader::new(&mut source)
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect();
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].data, [1, 2, 3]);
+        assert!(chunks[0].crc_ok);
+        assert_eq!(chunks[1].offset, 8 + 15);
+        assert_eq!(&chunks[1].type_name(), b"abCd");
+        assert!(!chunks[1].crc_ok);
+        assert!(chunks[1].is_private() && chunks[1].is_ancillary());
+        assert!(chunks[2].crc_ok);
+    }
+
+    #[test]
+    fn test_read_truncated_chunk() {
+        // The length claims far more data than the source holds.
+        let mut png = SIGNATURE.to_vec();
+        png.extend_from_slice(&MAX_CHUNK_LENGTH.to_be_bytes());
+        png.extend_from_slice(b"tEXtComment\0");
+
+        let mut source = Cursor::new(png);
+        let mut chunks = ChunkReader::new(&mut source).unwrap();
+        assert!(matches!(chunks.next(), Some(Err(ParserError::IOError(_)))));
+    }
+
+    #[test]
+    fn test_lazy_image_data() {
+        let png = split_png();
+        let expected = Parser::parse(&mut Cursor::new(&png)).unwrap();
+
+        let mut source = Cursor::new(&png);
+        let options = ParserOptions::new().with_image_data_handling(ImageDataHandling::Verify);
+        let mut info = Parser::parse_with_options(&mut source, &options).unwrap();
+
+        assert!(info.compressed_data.data.is_empty());
+        assert_eq!(info.compressed_data.chunk_count, 3);
+        assert_eq!(info.image_data_spans.len(), 3);
+        assert_eq!(info.image_data_spans[0].offset, 8 + 25 + 8);
+
+        info.load_image_data(&mut source).unwrap();
+        assert_eq!(info.compressed_data.data, expected.compressed_data.data);
+        assert_eq!(
+            info.decode().unwrap().pixels,
+            expected.decode().unwrap().pixels
+        );
+    }
+
+    #[test]
+    fn test_lazy_image_data_at_base() {
+        let png = split_png();
+        let expected = Parser::parse(&mut Cursor::new(&png)).unwrap();
+
+        // The PNG data stream follows other data in the source.
+        let mut source = Cursor::new([&b"container header"[..], &png].concat());
+        source.set_position(16);
+        let options = ParserOptions::new().with_image_data_handling(ImageDataHandling::Verify);
+        let mut info = Parser::parse_with_options(&mut source, &options).unwrap();
+        assert_eq!(info.image_data_spans[0].offset, 8 + 25 + 8);
+
+        let mut reader = info.image_data_reader_at(&mut source, 16);
+        assert_eq!(reader.read(&mut []).unwrap(), 0);
+        let mut first = [0u8; 2];
+        reader.read_exact(&mut first).unwrap();
+        assert_eq!(first, expected.compressed_data.data[..2]);
+
+        info.load_image_data_at(&mut source, 16).unwrap();
+        assert_eq!(info.compressed_data.data, expected.compressed_data.data);
+    }
+
+    #[test]
+    fn test_lazy_image_data_crc() {
+        let mut png = split_png();
+        let offset = 8 + 25 + 8;
+        png[offset + 4] ^= 0xff;
+
+        let verify = ParserOptions::new().with_image_data_handling(ImageDataHandling::Verify);
+        assert!(matches!(
+            Parser::parse_with_options(&mut Cursor::new(&png), &verify),
+            Err(ParserError::CorruptedData)
+        ));
+
+        let skip = ParserOptions::new().with_image_data_handling(ImageDataHandling::Skip);
+        let info = Parser::parse_with_options(&mut Cursor::new(&png), &skip).unwrap();
+        assert_eq!(info.image_data_spans.len(), 3);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::spec::*;
+
+/// Describes how the samples of an image are interpreted, taken from the chunk with the highest precedence.
+#[derive(Debug, Clone, Copy)]
+pub enum ColorInterpretation<'a> {
+    /// The color space is signalled by the `cICP` chunk, such as PQ or HLG encoded HDR content.
+    CodingIndependentCodePoints(&'a CodingIndependentCodePointsInfo),
+    /// The color space is described by the `iCCP` chunk.
+    IccProfile(&'a ICCProfileInfo),
+    /// The samples are in the sRGB color space with the rendering intent of the `sRGB` chunk.
+    StandardRgb(&'a RenderingIntent),
+    /// The samples are described by the `gAMA` and `cHRM` chunks, where at least one of them exists.
+    GammaAndChromaticity {
+        /// The gamma value.
+        gamma: Option<&'a GammaInfo>,
+        /// The primary chromaticities.
+        chromaticity: Option<&'a ChromaticityInfo>,
+    },
+    /// The image has no color space information.
+    Unspecified,
+}
+
+impl PngInfo {
+    /// Returns the color interpretation of the image.
+    ///
+    /// The chunks take precedence in the `cICP`, `iCCP`, `sRGB`, then `gAMA` and `cHRM` order, as the PNG
+    /// specification requires, so that the chunks kept for older decoders do not override the HDR signalling.
+    pub fn color_interpretation(&self) -> ColorInterpretation<'_> {
+        if let Some(code_points) = &self.coding_independent_code_points {
+            return ColorInterpretation::CodingIndependentCodePoints(code_points);
+        }
+
+        if let Some(icc_profile) = &self.icc_profile {
+            return ColorInterpretation::IccProfile(icc_profile);
+        }
+
+        if let Some(standard_rgb) = &self.standard_rgb {
+            return ColorInterpretation::StandardRgb(&standard_rgb.rendering_intent);
+        }
+
+        if self.gamma.is_some() || self.chromaticity.is_some() {
+            return ColorInterpretation::GammaAndChromaticity {
+                gamma: self.gamma.as_ref(),
+                chromaticity: self.chromaticity.as_ref(),
+            };
+        }
+
+        return ColorInterpretation::Unspecified;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::deflate::{self, CompressionLevel};
+    use crate::error::ParserError;
+    use crate::parser::Parser;
+    use crate::writer::Writer;
+
+    fn info() -> PngInfo {
+        let header = HeaderInfo {
+            width: 1,
+            height: 1,
+            bit_depth: 16,
+            color_type: ColorType::TrueColor,
+            compression_method: CompressionMethod::Deflate,
+            filter_method: FilterMethod::Adaptive,
+            interlace_method: InterlaceMethod::None,
+        };
+        return PngInfo::new(
+            header,
+            CompressedDataInfo {
+                chunk_count: 1,
+                chunk_lengths: Vec::new(),
+                data: deflate::compress(&[0; 7], CompressionLevel::Default),
+            },
+        );
+    }
+
+    fn mastering_display_color_volume(
+        max_luminance: f32,
+        min_luminance: f32,
+    ) -> MasteringDisplayColorVolumeInfo {
+        return MasteringDisplayColorVolumeInfo {
+            red: (0.708, 0.292),
+            green: (0.17, 0.797),
+            blue: (0.131, 0.046),
+            white_point: (0.3127, 0.329),
+            max_luminance,
+            min_luminance,
+        };
+    }
+
+    #[test]
+    fn test_code_points_take_precedence() {
+        let mut info = info();
+        info.gamma = Some(GammaInfo { gamma: 0.45455 });
+        info.standard_rgb = Some(StandardRGBInfo {
+            rendering_intent: RenderingIntent::Perceptual,
+        });
+        assert!(matches!(
+            info.color_interpretation(),
+            ColorInterpretation::StandardRgb(RenderingIntent::Perceptual)
+        ));
+
+        info.coding_independent_code_points = Some(CodingIndependentCodePointsInfo {
+            color_primaries: 9,
+            transfer_function: 16,
+            matrix_coefficients: 0,
+            is_full_range: true,
+        });
+        info.mastering_display_color_volume = Some(mastering_display_color_volume(1000.0, 0.0001));
+        info.content_light_level = Some(ContentLightLevelInfo {
+            max_content_light_level: 1000.0,
+            max_frame_average_light_level: 400.0,
+        });
+
+        let mut output = Vec::new();
+        Writer::write(&mut output, &info).unwrap();
+        let info = Parser::parse(&mut Cursor::new(&output)).unwrap();
+
+        let ColorInterpretation::CodingIndependentCodePoints(code_points) =
+            info.color_interpretation()
+        else {
+            panic!("cICP chunk is not preferred");
+        };
+        assert!(code_points.is_perceptual_quantizer());
+        assert_eq!(
+            info.mastering_display_color_volume.unwrap().red,
+            (0.708, 0.292)
+        );
+        assert_eq!(
+            info.content_light_level
+                .unwrap()
+                .max_frame_average_light_level,
+            400.0
+        );
+    }
+
+    #[test]
+    fn test_unknown_mastering_luminance() {
+        for (max_luminance, min_luminance) in [(0.0, 0.0), (0.0, 0.05), (1000.0, 0.0)] {
+            let mut info = info();
+            info.mastering_display_color_volume =
+                Some(mastering_display_color_volume(max_luminance, min_luminance));
+
+            let mut output = Vec::new();
+            Writer::write(&mut output, &info).unwrap();
+            let info = Parser::parse(&mut Cursor::new(&output)).unwrap();
+            assert_eq!(
+                info.mastering_display_color_volume.unwrap().min_luminance,
+                min_luminance
+            );
+        }
+
+        let mut info = info();
+        info.mastering_display_color_volume = Some(mastering_display_color_volume(0.05, 1000.0));
+        let mut output = Vec::new();
+        Writer::write(&mut output, &info).unwrap();
+        assert!(matches!(
+            Parser::parse(&mut Cursor::new(&output)),
+            Err(ParserError::InvalidFieldValue)
+        ));
+    }
+}
@@ -0,0 +1,264 @@
+use crate::image::{ImageLayout, read_sample};
+use crate::spec::{ColorType, TransparencyVariant};
+
+/// Converts packed pixel rows into 8-bit RGBA samples.
+///
+/// # Arguments
+/// * `layout` - The layout of the pixel rows.
+/// * `pixels` - The packed pixel rows.
+/// * `palette` - The palette entries used by indexed-color images.
+/// * `transparency` - The transparency applied to the pixels, if any.
+///
+/// # Returns
+/// * `Vec<u8>` - Four samples for each pixel in the red, green, blue, alpha order, or no samples if the layout is
+///   not valid.
+pub fn to_rgba8(
+    layout: &ImageLayout,
+    pixels: &[u8],
+    palette: &[(u8, u8, u8)],
+    transparency: Option<&TransparencyVariant>,
+) -> Vec<u8> {
+    let mut output = Vec::with_capacity(layout.width as usize * layout.height as usize * 4);
+    expand(layout, pixels, palette, transparency, 8, |sample| {
+        output.push(sample as u8)
+    });
+
+    return output;
+}
+
+/// Converts packed pixel rows into 16-bit RGBA samples.
+///
+/// # Arguments
+/// * `layout` - The layout of the pixel rows.
+/// * `pixels` - The packed pixel rows.
+/// * `palette` - The palette entries used by indexed-color images.
+/// * `transparency` - The transparency applied to the pixels, if any.
+///
+/// # Returns
+/// * `Vec<u16>` - Four samples for each pixel in the red, green, blue, alpha order, or no samples if the layout is
+///   not valid.
+pub fn to_rgba16(
+    layout: &ImageLayout,
+    pixels: &[u8],
+    palette: &[(u8, u8, u8)],
+    transparency: Option<&TransparencyVariant>,
+) -> Vec<u16> {
+    let mut output = Vec::with_capacity(layout.width as usize * layout.height as usize * 4);
+    expand(layout, pixels, palette, transparency, 16, |sample| {
+        output.push(sample)
+    });
+
+    return output;
+}
+
+/// Restores the 8-bit samples of Apple optimized images from BGR(A) with premultiplied alpha to RGB(A) with straight alpha.
+pub(crate) fn restore_cgbi_samples(layout: &ImageLayout, pixels: &mut [u8]) {
+    if layout.bit_depth != 8 {
+        return;
+    }
+
+    match layout.color_type {
+        ColorType::TrueColor => {
+            for pixel in pixels.chunks_exact_mut(3) {
+                pixel.swap(0, 2);
+            }
+        }
+        ColorType::TrueColorAlpha => {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+
+                let alpha = pixel[3] as u32;
+                if alpha != 0 && alpha != 255 {
+                    for sample in &mut pixel[..3] {
+                        *sample = ((*sample as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Scales a sample between bit depths, rounding to the nearest value.
+pub(crate) fn scale_sample(value: u16, from: u8, to: u8) -> u16 {
+    if from == to {
+        return value;
+    }
+
+    let from_max = (1u32 << from) - 1;
+    let to_max = (1u32 << to) - 1;
+    return ((value as u32 * to_max + from_max / 2) / from_max) as u16;
+}
+
+/// Expands every pixel to RGBA samples of the target bit depth and passes them to the provided sink.
+///
+/// Nothing is expanded for a layout whose samples cannot be read, and only the complete rows of the pixels are.
+fn expand<F: FnMut(u16)>(
+    layout: &ImageLayout,
+    pixels: &[u8],
+    palette: &[(u8, u8, u8)],
+    transparency: Option<&TransparencyVariant>,
+    target_depth: u8,
+    mut sink: F,
+) {
+    if !layout.is_valid() {
+        return;
+    }
+
+    let depth = layout.bit_depth;
+    let opaque = ((1u32 << target_depth) - 1) as u16;
+    let scale = |value: u16| scale_sample(value, depth, target_depth);
+
+    let stride = layout.stride();
+    if stride == 0 {
+        return;
+    }
+
+    for row in pixels.chunks_exact(stride).take(layout.height as usize) {
+        for x in 0..layout.width as usize {
+            let first = x * layout.channels as usize;
+            let sample = |channel: usize| read_sample(row, first + channel, depth);
+
+            let (r, g, b, a) = match layout.color_type {
+                ColorType::Grayscale => {
+                    let v = sample(0);
+                    let a = match transparency {
+                        Some(TransparencyVariant::Grayscale(key)) if *key == v => 0,
+                        _ => opaque,
+                    };
+                    (scale(v), scale(v), scale(v), a)
+                }
+                ColorType::TrueColor => {
+                    let (r, g, b) = (sample(0), sample(1), sample(2));
+                    let a = match transparency {
+                        Some(TransparencyVariant::TrueColor(kr, kg, kb))
+                            if (*kr, *kg, *kb) == (r, g, b) =>
+                        {
+                            0
+                        }
+                        _ => opaque,
+                    };
+                    (scale(r), scale(g), scale(b), a)
+                }
+                ColorType::IndexedColor => {
+                    let index = sample(0) as usize;
+                    // Out of range indices are rendered as opaque black instead of failing the whole image.
+                    let (r, g, b) = palette.get(index).copied().unwrap_or((0, 0, 0));
+                    let a = match transparency {
+                        Some(TransparencyVariant::IndexedColor(alphas)) => {
+                            alphas.get(index).copied().unwrap_or(255)
+                        }
+                        _ => 255,
+                    };
+                    (
+                        scale_sample(r as u16, 8, target_depth),
+                        scale_sample(g as u16, 8, target_depth),
+                        scale_sample(b as u16, 8, target_depth),
+                        scale_sample(a as u16, 8, target_depth),
+                    )
+                }
+                ColorType::GrayscaleAlpha => {
+                    let (v, a) = (sample(0), sample(1));
+                    (scale(v), scale(v), scale(v), scale(a))
+                }
+                ColorType::TrueColorAlpha => (
+                    scale(sample(0)),
+                    scale(sample(1)),
+                    scale(sample(2)),
+                    scale(sample(3)),
+                ),
+            };
+
+            sink(r);
+            sink(g);
+            sink(b);
+            sink(a);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_sample() {
+        assert_eq!(scale_sample(1, 1, 8), 255);
+        assert_eq!(scale_sample(2, 2, 8), 170);
+        assert_eq!(scale_sample(0xab, 8, 16), 0xabab);
+        assert_eq!(scale_sample(0xabab, 16, 8), 0xab);
+    }
+
+    #[test]
+    fn test_indexed_color_with_alpha_table() {
+        let layout = ImageLayout {
+            width: 4,
+            height: 1,
+            color_type: ColorType::IndexedColor,
+            channels: 1,
+            bit_depth: 2,
+        };
+        let palette = [(10, 20, 30), (40, 50, 60), (70, 80, 90)];
+        let transparency = TransparencyVariant::IndexedColor(vec![0, 128]);
+        let output = to_rgba8(&layout, &[0b00_01_10_11], &palette, Some(&transparency));
+        assert_eq!(
+            output,
+            [
+                10, 20, 30, 0, 40, 50, 60, 128, 70, 80, 90, 255, 0, 0, 0, 255
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_layout() {
+        let mut layout = ImageLayout {
+            width: 1,
+            height: 1,
+            color_type: ColorType::TrueColorAlpha,
+            channels: 4,
+            bit_depth: 8,
+        };
+        let pixels = [1, 2, 3, 4];
+        assert_eq!(to_rgba8(&layout, &pixels, &[], None), pixels);
+
+        layout.channels = 1;
+        assert!(to_rgba8(&layout, &pixels, &[], None).is_empty());
+        assert!(to_rgba16(&layout, &pixels, &[], None).is_empty());
+
+        layout.channels = 4;
+        layout.bit_depth = 12;
+        assert!(to_rgba8(&layout, &pixels, &[], None).is_empty());
+        assert!(to_rgba16(&layout, &pixels, &[], None).is_empty());
+    }
+
+    #[test]
+    fn test_restore_cgbi_samples() {
+        let layout = ImageLayout {
+            width: 2,
+            height: 1,
+            color_type: ColorType::TrueColorAlpha,
+            channels: 4,
+            bit_depth: 8,
+        };
+        let mut pixels = [10, 20, 30, 255, 0, 64, 128, 128];
+        restore_cgbi_samples(&layout, &mut pixels);
+        assert_eq!(pixels, [30, 20, 10, 255, 255, 128, 0, 128]);
+    }
+
+    #[test]
+    fn test_grayscale_color_key() {
+        let layout = ImageLayout {
+            width: 2,
+            height: 1,
+            color_type: ColorType::Grayscale,
+            channels: 1,
+            bit_depth: 4,
+        };
+        let transparency = TransparencyVariant::Grayscale(3);
+        let output = to_rgba16(&layout, &[0x3f], &[], Some(&transparency));
+        assert_eq!(
+            output,
+            [0x3333, 0x3333, 0x3333, 0, 0xffff, 0xffff, 0xffff, 0xffff]
+        );
+    }
+}
@@ -28,7 +28,19 @@ const fn make_table() -> [u32; 256] {
 /// * `u32` - The CRC32 checksum.
 #[inline]
 pub fn compute(data: &[u8]) -> u32 {
-    let mut crc = 0xffffffffu32;
+    return update(0, data);
+}
+
+/// Updates a running CRC32 checksum with the given data slice.
+///
+/// # Arguments
+/// * `crc` - The checksum computed so far, `0` for an empty input.
+/// * `data` - A byte slice to append to the checksum.
+///
+/// # Returns
+/// * `u32` - The updated CRC32 checksum.
+pub fn update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = crc ^ 0xffffffffu32;
 
     for &byte in data {
         let index = ((crc ^ (byte as u32)) & 0xffu32) as usize;
@@ -0,0 +1,699 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::adler32;
+use crate::inflate::{
+    CODE_LENGTH_ORDER, DISTANCE_BASE, DISTANCE_EXTRA, LENGTH_BASE, LENGTH_EXTRA, MAX_CODE_LENGTH,
+};
+
+/// The size of the sliding window.
+const WINDOW_SIZE: usize = 32768;
+/// The shortest match that can be encoded.
+const MIN_MATCH: usize = 3;
+/// The longest match that can be encoded.
+const MAX_MATCH: usize = 258;
+/// The number of bits of the hash used to find match candidates.
+const HASH_BITS: u32 = 15;
+/// The number of tokens collected before a block is emitted.
+const BLOCK_TOKENS: usize = 16384;
+/// The largest number of bytes in a stored block.
+const MAX_STORED_LENGTH: usize = 65535;
+/// The end-of-block symbol.
+const END_OF_BLOCK: usize = 256;
+
+/// Describes the trade-off between compression speed and compression ratio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionLevel {
+    /// No compression, the data is only wrapped in stored blocks.
+    Stored,
+    /// The fastest compression with short match searches.
+    Fastest,
+    /// A fast compression with moderate match searches.
+    Fast,
+    /// A balanced compression with lazy matching.
+    Default,
+    /// The maximum compression with exhaustive match searches.
+    Maximum,
+}
+
+impl CompressionLevel {
+    /// Returns the maximum chain length, the length that stops the search and whether lazy matching is used.
+    fn parameters(&self) -> (usize, usize, bool) {
+        return match self {
+            CompressionLevel::Stored => (0, 0, false),
+            CompressionLevel::Fastest => (4, 16, false),
+            CompressionLevel::Fast => (32, 64, false),
+            CompressionLevel::Default => (128, 128, true),
+            CompressionLevel::Maximum => (4096, MAX_MATCH, true),
+        };
+    }
+
+    /// Returns the value of the `FLEVEL` field of the zlib header.
+    fn zlib_level(&self) -> u8 {
+        return match self {
+            CompressionLevel::Stored | CompressionLevel::Fastest => 0,
+            CompressionLevel::Fast => 1,
+            CompressionLevel::Default => 2,
+            CompressionLevel::Maximum => 3,
+        };
+    }
+}
+
+/// Compresses the provided data into a zlib stream.
+///
+/// # Arguments
+/// * `data` - A byte slice to compress.
+/// * `level` - The compression level.
+///
+/// # Returns
+/// * `Vec<u8>` - The zlib stream with its header and Adler-32 checksum.
+pub fn compress(data: &[u8], level: CompressionLevel) -> Vec<u8> {
+    let cmf = 0x78u8;
+    let mut flg = level.zlib_level() << 6;
+    flg += 31 - ((((cmf as u16) << 8) | flg as u16) % 31) as u8;
+
+    let mut output = vec![cmf, flg];
+    output.extend_from_slice(&compress_raw(data, level));
+    output.extend_from_slice(&adler32::compute(data).to_be_bytes());
+
+    return output;
+}
+
+/// Compresses the provided data into a raw deflate stream without the zlib wrapper.
+///
+/// # Arguments
+/// * `data` - A byte slice to compress.
+/// * `level` - The compression level.
+///
+/// # Returns
+/// * `Vec<u8>` - The deflate stream.
+pub fn compress_raw(data: &[u8], level: CompressionLevel) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+
+    if level == CompressionLevel::Stored {
+        write_stored(&mut writer, data, true);
+        return writer.finish();
+    }
+
+    let mut matcher = Matcher::new(data, level);
+    let mut tokens = Vec::with_capacity(BLOCK_TOKENS);
+    let mut block_start = 0;
+
+    loop {
+        let position = matcher.next_tokens(&mut tokens, BLOCK_TOKENS);
+        let is_final = position >= data.len();
+
+        write_block(&mut writer, &tokens, &data[block_start..position], is_final);
+
+        if is_final {
+            break;
+        }
+
+        tokens.clear();
+        block_start = position;
+    }
+
+    return writer.finish();
+}
+
+/// Represents a piece of the compressed data before it is entropy coded.
+#[derive(Debug, Clone, Copy)]
+enum Token {
+    Literal(u8),
+    Match { length: u16, distance: u16 },
+}
+
+/// Finds repeated sequences using hash chains over the sliding window.
+struct Matcher<'a> {
+    data: &'a [u8],
+    position: usize,
+    head: Vec<i32>,
+    previous: Vec<i32>,
+    max_chain: usize,
+    nice_length: usize,
+    lazy: bool,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(data: &'a [u8], level: CompressionLevel) -> Self {
+        let (max_chain, nice_length, lazy) = level.parameters();
+
+        return Self {
+            data,
+            position: 0,
+            head: vec![-1; 1 << HASH_BITS],
+            previous: vec![-1; WINDOW_SIZE],
+            max_chain,
+            nice_length,
+            lazy,
+        };
+    }
+
+    fn hash(&self, position: usize) -> usize {
+        let bytes = &self.data[position..position + MIN_MATCH];
+        let value = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        return (value.wrapping_mul(0x9e3779b1) >> (32 - HASH_BITS)) as usize;
+    }
+
+    fn insert(&mut self, position: usize) {
+        if position + MIN_MATCH > self.data.len() {
+            return;
+        }
+
+        let hash = self.hash(position);
+        self.previous[position % WINDOW_SIZE] = self.head[hash];
+        self.head[hash] = position as i32;
+    }
+
+    /// Returns the length and distance of the longest match found for the provided position.
+    fn find(&self, position: usize) -> (usize, usize) {
+        let max_length = MAX_MATCH.min(self.data.len() - position);
+        if max_length < MIN_MATCH {
+            return (0, 0);
+        }
+
+        let mut best = (0, 0);
+        let mut candidate = self.head[self.hash(position)];
+        let mut chain = self.max_chain;
+
+        while candidate >= 0 && chain > 0 {
+            let start = candidate as usize;
+            let distance = position - start;
+            if distance > WINDOW_SIZE {
+                break;
+            }
+
+            // Checking the byte that would extend the best match first skips most candidates early.
+            if self.data[start + best.0] == self.data[position + best.0] {
+                let length = self.data[start..start + max_length]
+                    .iter()
+                    .zip(&self.data[position..position + max_length])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+
+                if length > best.0 {
+                    best = (length, distance);
+                    if length >= self.nice_length || length == max_length {
+                        break;
+                    }
+                }
+            }
+
+            let next = self.previous[start % WINDOW_SIZE];
+            if next >= candidate {
+                break;
+            }
+
+            candidate = next;
+            chain -= 1;
+        }
+
+        if best.0 < MIN_MATCH {
+            return (0, 0);
+        }
+
+        return best;
+    }
+
+    /// Appends tokens until the limit is reached or the data ends, returning the position reached.
+    fn next_tokens(&mut self, tokens: &mut Vec<Token>, limit: usize) -> usize {
+        while self.position < self.data.len() && tokens.len() < limit {
+            let position = self.position;
+            let (length, distance) = self.find(position);
+
+            if length == 0 {
+                tokens.push(Token::Literal(self.data[position]));
+                self.insert(position);
+                self.position += 1;
+                continue;
+            }
+
+            self.insert(position);
+
+            // A longer match at the next position is worth delaying the current one by a literal.
+            if self.lazy && length < self.nice_length && self.find(position + 1).0 > length {
+                tokens.push(Token::Literal(self.data[position]));
+                self.position += 1;
+                continue;
+            }
+
+            tokens.push(Token::Match {
+                length: length as u16,
+                distance: distance as u16,
+            });
+
+            for next in position + 1..position + length {
+                self.insert(next);
+            }
+            self.position += length;
+        }
+
+        return self.position;
+    }
+}
+
+/// Writes bits in the least-significant-bit-first order used by deflate.
+struct BitWriter {
+    output: Vec<u8>,
+    bit_buffer: u64,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        return Self {
+            output: Vec::new(),
+            bit_buffer: 0,
+            bit_count: 0,
+        };
+    }
+
+    fn write_bits(&mut self, value: u32, count: u32) {
+        self.bit_buffer |= (value as u64) << self.bit_count;
+        self.bit_count += count;
+
+        while self.bit_count >= 8 {
+            self.output.push(self.bit_buffer as u8);
+            self.bit_buffer >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    fn align_to_byte(&mut self) {
+        if self.bit_count > 0 {
+            self.write_bits(0, 8 - self.bit_count);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.align_to_byte();
+        return self.output;
+    }
+}
+
+/// Represents a Huffman code by the length and the bit-reversed code of each symbol.
+struct HuffmanCode {
+    lengths: Vec<u8>,
+    codes: Vec<u16>,
+}
+
+impl HuffmanCode {
+    fn from_lengths(lengths: Vec<u8>) -> Self {
+        let mut counts = [0u16; MAX_CODE_LENGTH + 1];
+        for &length in &lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut next_code = [0u16; MAX_CODE_LENGTH + 1];
+        let mut code = 0u16;
+        for length in 1..=MAX_CODE_LENGTH {
+            code = (code + counts[length - 1]) << 1;
+            next_code[length] = code;
+        }
+
+        let codes = lengths
+            .iter()
+            .map(|&length| {
+                if length == 0 {
+                    return 0;
+                }
+
+                let code = next_code[length as usize];
+                next_code[length as usize] += 1;
+                code.reverse_bits() >> (16 - length as u32)
+            })
+            .collect();
+
+        return Self { lengths, codes };
+    }
+
+    fn from_frequencies(frequencies: &[u32], limit: u8) -> Self {
+        return Self::from_lengths(huffman_lengths(frequencies, limit));
+    }
+
+    fn fixed_literals() -> Self {
+        let mut lengths = vec![8u8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        return Self::from_lengths(lengths);
+    }
+
+    fn fixed_distances() -> Self {
+        return Self::from_lengths(vec![5u8; 30]);
+    }
+
+    fn write(&self, writer: &mut BitWriter, symbol: usize) {
+        writer.write_bits(self.codes[symbol] as u32, self.lengths[symbol] as u32);
+    }
+
+    /// Returns the number of bits needed to encode the symbols with the provided frequencies.
+    fn cost(&self, frequencies: &[u32]) -> u64 {
+        return frequencies
+            .iter()
+            .zip(&self.lengths)
+            .map(|(&frequency, &length)| frequency as u64 * length as u64)
+            .sum();
+    }
+}
+
+/// Computes Huffman code lengths no longer than the limit for the provided symbol frequencies.
+fn huffman_lengths(frequencies: &[u32], limit: u8) -> Vec<u8> {
+    let mut lengths = vec![0u8; frequencies.len()];
+    let mut frequencies = frequencies.to_vec();
+
+    let used: Vec<usize> = (0..frequencies.len())
+        .filter(|&symbol| frequencies[symbol] > 0)
+        .collect();
+
+    match used.len() {
+        0 => return lengths,
+        1 => {
+            lengths[used[0]] = 1;
+            return lengths;
+        }
+        _ => {}
+    }
+
+    loop {
+        // Leaves are the used symbols; internal nodes are appended after them with their children.
+        let mut children: Vec<(usize, usize)> = Vec::with_capacity(used.len());
+        let mut heap: BinaryHeap<Reverse<(u64, usize)>> = used
+            .iter()
+            .enumerate()
+            .map(|(node, &symbol)| Reverse((frequencies[symbol] as u64, node)))
+            .collect();
+
+        while heap.len() > 1 {
+            let Reverse((left_weight, left)) = heap.pop().unwrap();
+            let Reverse((right_weight, right)) = heap.pop().unwrap();
+            children.push((left, right));
+            heap.push(Reverse((
+                left_weight + right_weight,
+                used.len() + children.len() - 1,
+            )));
+        }
+
+        let mut depths = vec![0u8; used.len() + children.len()];
+        for internal in (0..children.len()).rev() {
+            let depth = depths[used.len() + internal] + 1;
+            let (left, right) = children[internal];
+            depths[left] = depth;
+            depths[right] = depth;
+        }
+
+        if depths[..used.len()].iter().all(|&depth| depth <= limit) {
+            for (node, &symbol) in used.iter().enumerate() {
+                lengths[symbol] = depths[node];
+            }
+            return lengths;
+        }
+
+        // Flattening the distribution shortens the longest codes until they fit the limit.
+        for &symbol in &used {
+            frequencies[symbol] = frequencies[symbol].div_ceil(2);
+        }
+    }
+}
+
+/// Returns the symbol index and extra bits of the provided match length.
+fn length_symbol(length: usize) -> (usize, u32) {
+    let index = LENGTH_BASE.partition_point(|&base| base as usize <= length) - 1;
+    return (index, (length - LENGTH_BASE[index] as usize) as u32);
+}
+
+/// Returns the symbol and extra bits of the provided match distance.
+fn distance_symbol(distance: usize) -> (usize, u32) {
+    let index = DISTANCE_BASE.partition_point(|&base| base as usize <= distance) - 1;
+    return (index, (distance - DISTANCE_BASE[index] as usize) as u32);
+}
+
+/// Writes the data as one or more stored blocks.
+fn write_stored(writer: &mut BitWriter, data: &[u8], is_final: bool) {
+    let mut blocks = data.chunks(MAX_STORED_LENGTH).peekable();
+
+    if blocks.peek().is_none() {
+        writer.write_bits(is_final as u32, 1);
+        writer.write_bits(0, 2);
+        writer.align_to_byte();
+        writer.output.extend_from_slice(&[0x00, 0x00, 0xff, 0xff]);
+        return;
+    }
+
+    while let Some(block) = blocks.next() {
+        let is_last = is_final && blocks.peek().is_none();
+        writer.write_bits(is_last as u32, 1);
+        writer.write_bits(0, 2);
+        writer.align_to_byte();
+
+        let length = block.len() as u16;
+        writer.output.extend_from_slice(&length.to_le_bytes());
+        writer.output.extend_from_slice(&(!length).to_le_bytes());
+        writer.output.extend_from_slice(block);
+    }
+}
+
+/// Writes the tokens as the cheapest of a stored, fixed Huffman or dynamic Huffman block.
+fn write_block(writer: &mut BitWriter, tokens: &[Token], data: &[u8], is_final: bool) {
+    let mut literal_frequencies = [0u32; 286];
+    let mut distance_frequencies = [0u32; 30];
+    let mut extra_bits = 0u64;
+
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => literal_frequencies[byte as usize] += 1,
+            Token::Match { length, distance } => {
+                let (length_index, _) = length_symbol(length as usize);
+                let (distance_index, _) = distance_symbol(distance as usize);
+                literal_frequencies[257 + length_index] += 1;
+                distance_frequencies[distance_index] += 1;
+                extra_bits += LENGTH_EXTRA[length_index] as u64;
+                extra_bits += DISTANCE_EXTRA[distance_index] as u64;
+            }
+        }
+    }
+    literal_frequencies[END_OF_BLOCK] += 1;
+
+    let fixed_literals = HuffmanCode::fixed_literals();
+    let fixed_distances = HuffmanCode::fixed_distances();
+    let fixed_cost = 3
+        + fixed_literals.cost(&literal_frequencies)
+        + fixed_distances.cost(&distance_frequencies)
+        + extra_bits;
+
+    let dynamic_literals = HuffmanCode::from_frequencies(&literal_frequencies, 15);
+    let dynamic_distances = HuffmanCode::from_frequencies(&distance_frequencies, 15);
+    let header = DynamicHeader::new(&dynamic_literals, &dynamic_distances);
+    let dynamic_cost = 3
+        + header.cost()
+        + dynamic_literals.cost(&literal_frequencies)
+        + dynamic_distances.cost(&distance_frequencies)
+        + extra_bits;
+
+    let stored_blocks = data.len().div_ceil(MAX_STORED_LENGTH).max(1) as u64;
+    let stored_cost = stored_blocks * (3 + 7 + 32) + data.len() as u64 * 8;
+
+    if stored_cost < fixed_cost.min(dynamic_cost) {
+        write_stored(writer, data, is_final);
+        return;
+    }
+
+    writer.write_bits(is_final as u32, 1);
+
+    if fixed_cost <= dynamic_cost {
+        writer.write_bits(1, 2);
+        write_tokens(writer, tokens, &fixed_literals, &fixed_distances);
+    } else {
+        writer.write_bits(2, 2);
+        header.write(writer);
+        write_tokens(writer, tokens, &dynamic_literals, &dynamic_distances);
+    }
+}
+
+fn write_tokens(
+    writer: &mut BitWriter,
+    tokens: &[Token],
+    literals: &HuffmanCode,
+    distances: &HuffmanCode,
+) {
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => literals.write(writer, byte as usize),
+            Token::Match { length, distance } => {
+                let (length_index, length_extra) = length_symbol(length as usize);
+                literals.write(writer, 257 + length_index);
+                writer.write_bits(length_extra, LENGTH_EXTRA[length_index] as u32);
+
+                let (distance_index, distance_extra) = distance_symbol(distance as usize);
+                distances.write(writer, distance_index);
+                writer.write_bits(distance_extra, DISTANCE_EXTRA[distance_index] as u32);
+            }
+        }
+    }
+
+    literals.write(writer, END_OF_BLOCK);
+}
+
+/// Represents the code lengths of a dynamic block, run-length encoded with the code length alphabet.
+struct DynamicHeader {
+    literal_count: usize,
+    distance_count: usize,
+    code_length_count: usize,
+    /// The code length symbols with their extra bits values.
+    symbols: Vec<(u8, u8)>,
+    code: HuffmanCode,
+}
+
+impl DynamicHeader {
+    fn new(literals: &HuffmanCode, distances: &HuffmanCode) -> Self {
+        let literal_count = 257.max(last_used(&literals.lengths));
+        let distance_count = 1.max(last_used(&distances.lengths));
+
+        let lengths: Vec<u8> = literals.lengths[..literal_count]
+            .iter()
+            .chain(&distances.lengths[..distance_count])
+            .copied()
+            .collect();
+
+        let mut symbols = Vec::new();
+        let mut index = 0;
+        while index < lengths.len() {
+            let length = lengths[index];
+            let run = lengths[index..]
+                .iter()
+                .take_while(|&&other| other == length)
+                .count();
+
+            if length == 0 && run >= 11 {
+                let run = run.min(138);
+                symbols.push((18, (run - 11) as u8));
+                index += run;
+            } else if length == 0 && run >= 3 {
+                symbols.push((17, (run - 3) as u8));
+                index += run;
+            } else if length != 0 && run >= 4 {
+                // The first length is sent as is, the rest repeat it in runs of at most six.
+                let run = (run - 1).min(6);
+                symbols.push((length, 0));
+                symbols.push((16, (run - 3) as u8));
+                index += run + 1;
+            } else {
+                symbols.push((length, 0));
+                index += 1;
+            }
+        }
+
+        let mut frequencies = [0u32; 19];
+        for &(symbol, _) in &symbols {
+            frequencies[symbol as usize] += 1;
+        }
+
+        let code = HuffmanCode::from_frequencies(&frequencies, 7);
+        let code_length_count = 4.max(
+            CODE_LENGTH_ORDER
+                .iter()
+                .rposition(|&symbol| code.lengths[symbol] != 0)
+                .map_or(0, |index| index + 1),
+        );
+
+        return Self {
+            literal_count,
+            distance_count,
+            code_length_count,
+            symbols,
+            code,
+        };
+    }
+
+    fn extra_bits(symbol: u8) -> u32 {
+        return match symbol {
+            16 => 2,
+            17 => 3,
+            18 => 7,
+            _ => 0,
+        };
+    }
+
+    fn cost(&self) -> u64 {
+        let symbols: u64 = self
+            .symbols
+            .iter()
+            .map(|&(symbol, _)| {
+                self.code.lengths[symbol as usize] as u64 + Self::extra_bits(symbol) as u64
+            })
+            .sum();
+
+        return 5 + 5 + 4 + 3 * self.code_length_count as u64 + symbols;
+    }
+
+    fn write(&self, writer: &mut BitWriter) {
+        writer.write_bits((self.literal_count - 257) as u32, 5);
+        writer.write_bits((self.distance_count - 1) as u32, 5);
+        writer.write_bits((self.code_length_count - 4) as u32, 4);
+
+        for &symbol in &CODE_LENGTH_ORDER[..self.code_length_count] {
+            writer.write_bits(self.code.lengths[symbol] as u32, 3);
+        }
+
+        for &(symbol, extra) in &self.symbols {
+            self.code.write(writer, symbol as usize);
+            writer.write_bits(extra as u32, Self::extra_bits(symbol));
+        }
+    }
+}
+
+/// Returns the number of symbols up to and including the last one with a code.
+fn last_used(lengths: &[u8]) -> usize {
+    return lengths
+        .iter()
+        .rposition(|&length| length != 0)
+        .map_or(0, |index| index + 1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inflate;
+
+    fn sample_data() -> Vec<u8> {
+        let mut data = Vec::new();
+        for i in 0..20000u32 {
+            data.extend_from_slice(b"the quick brown fox ");
+            data.push((i.wrapping_mul(2654435761) >> 24) as u8);
+        }
+        return data;
+    }
+
+    #[test]
+    fn test_compress_round_trip_all_levels() {
+        let data = sample_data();
+        for level in [
+            CompressionLevel::Stored,
+            CompressionLevel::Fastest,
+            CompressionLevel::Fast,
+            CompressionLevel::Default,
+            CompressionLevel::Maximum,
+        ] {
+            let compressed = compress(&data, level);
+            assert_eq!(inflate::decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_compress_empty_input() {
+        for level in [CompressionLevel::Stored, CompressionLevel::Default] {
+            let compressed = compress(&[], level);
+            assert_eq!(inflate::decompress(&compressed).unwrap(), b"");
+        }
+    }
+
+    #[test]
+    fn test_higher_levels_compress_better() {
+        let data = sample_data();
+        let stored = compress(&data, CompressionLevel::Stored).len();
+        let fastest = compress(&data, CompressionLevel::Fastest).len();
+        let maximum = compress(&data, CompressionLevel::Maximum).len();
+        assert!(fastest < stored);
+        assert!(maximum <= fastest);
+    }
+}
@@ -0,0 +1,576 @@
+use std::io::Write;
+
+use crate::deflate::{self, CompressionLevel};
+use crate::error::WriterError;
+use crate::filter;
+use crate::interlace;
+use crate::spec::*;
+use crate::writer::Writer;
+
+/// The filter types tried by the adaptive strategies.
+const FILTER_TYPES: [FilterType; 5] = [
+    FilterType::None,
+    FilterType::Sub,
+    FilterType::Up,
+    FilterType::Average,
+    FilterType::Paeth,
+];
+
+/// Describes how the filter type of each scanline is chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterStrategy {
+    /// The same filter type is applied to every scanline.
+    Fixed(FilterType),
+    /// The filter type with the minimum sum of absolute differences is chosen for each scanline.
+    MinimumSum,
+    /// Every filter type is compressed on trial and the smallest one is chosen for each scanline.
+    BruteForce,
+}
+
+/// Represents the options of encoding image data.
+#[derive(Debug, Clone, Copy)]
+pub struct EncoderOptions {
+    /// The strategy for choosing the filter type of each scanline.
+    pub filter_strategy: FilterStrategy,
+    /// The compression level of the image data.
+    pub compression_level: CompressionLevel,
+    /// The maximum number of bytes in each `IDAT` chunk.
+    pub max_chunk_size: usize,
+}
+
+impl Default for EncoderOptions {
+    fn default() -> Self {
+        return Self {
+            filter_strategy: FilterStrategy::MinimumSum,
+            compression_level: CompressionLevel::Default,
+            max_chunk_size: 8192,
+        };
+    }
+}
+
+/// Represents a frame of an animation to encode.
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationFrame<'a> {
+    /// The 8-bit RGBA samples of the whole canvas.
+    pub pixels: &'a [u8],
+    /// The numerator of the frame delay in seconds.
+    pub delay_numerator: u16,
+    /// The denominator of the frame delay in seconds.
+    pub delay_denominator: u16,
+}
+
+/// Represents a rectangular region of the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Region {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Encodes raw pixels and writes them as a complete PNG image to the provided sink.
+///
+/// # Arguments
+/// * `sink` - The sink to write the image to.
+/// * `header` - The header describing the image layout.
+/// * `palette` - The palette, required for indexed-color images.
+/// * `pixels` - The unfiltered scanlines packed one after another, in the same layout as `Image::pixels`.
+/// * `options` - The encoding options.
+pub fn encode<Sink: Write>(
+    sink: &mut Sink,
+    header: HeaderInfo,
+    palette: Option<PaletteInfo>,
+    pixels: &[u8],
+    options: &EncoderOptions,
+) -> Result<(), WriterError> {
+    if header.color_type == ColorType::IndexedColor && palette.is_none() {
+        return Err(WriterError::MissingRequiredChunk(chunk_ids::PLTE));
+    }
+
+    let compressed_data = encode_image_data(&header, pixels, options)?;
+
+    let mut info = PngInfo::new(header, compressed_data);
+    info.palette = palette;
+
+    return Writer::write(sink, &info);
+}
+
+/// Encodes a sequence of RGBA frames and writes them as an animated PNG image to the provided sink.
+///
+/// The first frame is stored as the default image. Every following frame only stores the smallest region that
+/// differs from the canvas, with the dispose operation of the previous frame chosen to keep that region small.
+///
+/// # Arguments
+/// * `sink` - The sink to write the image to.
+/// * `width` - The width of the canvas.
+/// * `height` - The height of the canvas.
+/// * `frames` - The frames in the order they are shown.
+/// * `play_count` - The number of times to loop the animation, or 0 to loop infinitely.
+/// * `options` - The encoding options of the frame data.
+pub fn encode_animation<Sink: Write>(
+    sink: &mut Sink,
+    width: u32,
+    height: u32,
+    frames: &[AnimationFrame],
+    play_count: u32,
+    options: &EncoderOptions,
+) -> Result<(), WriterError> {
+    if frames.is_empty() || frames.len() > u32::MAX as usize {
+        return Err(WriterError::InvalidFieldValue);
+    }
+
+    let canvas_size = width as usize * height as usize * 4;
+    if frames.iter().any(|frame| frame.pixels.len() != canvas_size) {
+        return Err(WriterError::InvalidImageDataLength);
+    }
+
+    let canvas = Region {
+        x: 0,
+        y: 0,
+        width,
+        height,
+    };
+
+    let mut info = PngInfo::new(
+        rgba_header(width, height),
+        encode_image_data(&rgba_header(width, height), frames[0].pixels, options)?,
+    );
+    info.animation_control = Some(AnimationControlInfo {
+        frame_count: frames.len() as u32,
+        play_count,
+    });
+    info.animation_frames.push(FrameInfo {
+        control: frame_control(&frames[0], canvas, BlendOp::Source),
+        data: None,
+    });
+
+    // The canvas before the previous frame is rendered, used when the previous frame is disposed to it.
+    let mut before = vec![0u8; canvas_size];
+    let mut region = canvas;
+
+    for (index, frame) in frames.iter().enumerate().skip(1) {
+        let previous = frames[index - 1].pixels;
+
+        let mut cleared = previous.to_vec();
+        fill_region(&mut cleared, width, region, 0);
+
+        let mut candidates = vec![
+            (DisposeOp::None, previous.to_vec()),
+            (DisposeOp::Background, cleared),
+        ];
+
+        // Disposing the first frame to the previous contents is treated as clearing it.
+        if index > 1 {
+            candidates.push((DisposeOp::Previous, before));
+        }
+
+        let (dispose_op, base, changed) = candidates
+            .into_iter()
+            .map(|(dispose_op, base)| {
+                let changed = changed_region(&base, frame.pixels, width, height);
+                (dispose_op, base, changed)
+            })
+            .min_by_key(|(_, _, changed)| changed.width as u64 * changed.height as u64)
+            .unwrap();
+
+        info.animation_frames[index - 1].control.dispose_op = dispose_op;
+
+        // Unchanged pixels can be left transparent when every changed pixel is opaque.
+        let blend_op = match region_pixels(frame.pixels, width, changed)
+            .zip(region_pixels(&base, width, changed))
+            .all(|(current, base)| current == base || current[3] == 255)
+        {
+            true => BlendOp::Over,
+            false => BlendOp::Source,
+        };
+
+        let pixels: Vec<u8> = region_pixels(frame.pixels, width, changed)
+            .zip(region_pixels(&base, width, changed))
+            .flat_map(|(current, base)| match blend_op {
+                BlendOp::Over if current == base => [0; 4],
+                _ => [current[0], current[1], current[2], current[3]],
+            })
+            .collect();
+
+        let compressed_data = encode_image_data(
+            &rgba_header(changed.width, changed.height),
+            &pixels,
+            options,
+        )?;
+
+        info.animation_frames.push(FrameInfo {
+            control: frame_control(frame, changed, blend_op),
+            data: Some(FrameDataInfo {
+                chunk_count: compressed_data.chunk_count,
+                chunk_lengths: compressed_data.chunk_lengths,
+                data: compressed_data.data,
+            }),
+        });
+
+        before = base;
+        region = changed;
+    }
+
+    // The sequence numbers are shared by the frame controls and every frame data chunk, in the order they are written.
+    let mut sequence_number = 0;
+    for frame in &mut info.animation_frames {
+        frame.control.sequence_number = sequence_number;
+        sequence_number += 1 + frame.data.as_ref().map_or(0, |data| data.chunk_count);
+    }
+
+    return Writer::write(sink, &info);
+}
+
+/// Filters, interlaces if necessary, and compresses raw pixels into `IDAT` data.
+///
+/// # Arguments
+/// * `header` - The header describing the image layout.
+/// * `pixels` - The unfiltered scanlines packed one after another, in the same layout as `Image::pixels`.
+/// * `options` - The encoding options.
+///
+/// # Returns
+/// * `CompressedDataInfo` - The compressed data split into chunks of at most `max_chunk_size` bytes.
+pub fn encode_image_data(
+    header: &HeaderInfo,
+    pixels: &[u8],
+    options: &EncoderOptions,
+) -> Result<CompressedDataInfo, WriterError> {
+    if header.width == 0 || header.height == 0 || !header.has_valid_bit_depth() {
+        return Err(WriterError::InvalidFieldValue);
+    }
+
+    if pixels.len() != header.stride() * header.height as usize {
+        return Err(WriterError::InvalidImageDataLength);
+    }
+
+    let bits_per_pixel = header.bits_per_pixel();
+    let mut filtered = Vec::new();
+
+    match header.interlace_method {
+        InterlaceMethod::None => filter_scanlines(
+            pixels,
+            header.stride(),
+            header.bytes_per_pixel(),
+            options,
+            &mut filtered,
+        ),
+        InterlaceMethod::Adam7 => {
+            for pass in 0..interlace::ADAM7_PASSES.len() {
+                let (pass_width, _) = interlace::pass_size(pass, header.width, header.height);
+                let pass_pixels = interlace::extract_pass(
+                    pixels,
+                    header.width,
+                    header.height,
+                    bits_per_pixel,
+                    pass,
+                );
+
+                filter_scanlines(
+                    &pass_pixels,
+                    filter::stride(pass_width, bits_per_pixel),
+                    header.bytes_per_pixel(),
+                    options,
+                    &mut filtered,
+                );
+            }
+        }
+    }
+
+    let data = deflate::compress(&filtered, options.compression_level);
+    let chunk_count = data.len().div_ceil(options.max_chunk_size.max(1)).max(1);
+
+    return Ok(CompressedDataInfo {
+        chunk_count: chunk_count as u32,
+        chunk_lengths: Vec::new(),
+        data,
+    });
+}
+
+/// Creates the header of a non-interlaced 8-bit RGBA image.
+fn rgba_header(width: u32, height: u32) -> HeaderInfo {
+    return HeaderInfo {
+        width,
+        height,
+        bit_depth: 8,
+        color_type: ColorType::TrueColorAlpha,
+        compression_method: CompressionMethod::Deflate,
+        filter_method: FilterMethod::Adaptive,
+        interlace_method: InterlaceMethod::None,
+    };
+}
+
+/// Creates the frame control of a frame covering the provided region.
+fn frame_control(frame: &AnimationFrame, region: Region, blend_op: BlendOp) -> FrameControlInfo {
+    return FrameControlInfo {
+        sequence_number: 0,
+        width: region.width,
+        height: region.height,
+        x_offset: region.x,
+        y_offset: region.y,
+        delay_numerator: frame.delay_numerator,
+        delay_denominator: frame.delay_denominator,
+        dispose_op: DisposeOp::None,
+        blend_op,
+    };
+}
+
+/// Finds the smallest region containing every pixel that differs between two canvases.
+///
+/// Identical canvases produce a single pixel region, since frames cannot be empty.
+fn changed_region(base: &[u8], current: &[u8], width: u32, height: u32) -> Region {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+
+    for y in 0..height {
+        for x in 0..width {
+            let offset = (y as usize * width as usize + x as usize) * 4;
+            if base[offset..offset + 4] != current[offset..offset + 4] {
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+        }
+    }
+
+    if min_x == u32::MAX {
+        return Region {
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+        };
+    }
+
+    return Region {
+        x: min_x,
+        y: min_y,
+        width: max_x - min_x + 1,
+        height: max_y - min_y + 1,
+    };
+}
+
+/// Returns an iterator over the RGBA pixels of a canvas inside the provided region.
+fn region_pixels(canvas: &[u8], width: u32, region: Region) -> impl Iterator<Item = &[u8]> {
+    let stride = width as usize * 4;
+    return canvas
+        .chunks_exact(stride)
+        .skip(region.y as usize)
+        .take(region.height as usize)
+        .flat_map(move |row| {
+            row[region.x as usize * 4..(region.x + region.width) as usize * 4].chunks_exact(4)
+        });
+}
+
+/// Sets every sample of a canvas inside the provided region to the provided value.
+fn fill_region(canvas: &mut [u8], width: u32, region: Region, value: u8) {
+    let stride = width as usize * 4;
+    for row in canvas
+        .chunks_exact_mut(stride)
+        .skip(region.y as usize)
+        .take(region.height as usize)
+    {
+        row[region.x as usize * 4..(region.x + region.width) as usize * 4].fill(value);
+    }
+}
+
+/// Filters the scanlines of a (reduced) image with the filter types chosen by the strategy.
+fn filter_scanlines(
+    pixels: &[u8],
+    stride: usize,
+    bytes_per_pixel: usize,
+    options: &EncoderOptions,
+    output: &mut Vec<u8>,
+) {
+    if stride == 0 {
+        return;
+    }
+
+    let mut previous = vec![0u8; stride];
+    let mut candidate = Vec::with_capacity(stride + 1);
+    let mut best = Vec::with_capacity(stride + 1);
+
+    for current in pixels.chunks_exact(stride) {
+        match options.filter_strategy {
+            FilterStrategy::Fixed(filter_type) => {
+                filter::filter_row(filter_type, bytes_per_pixel, &previous, current, output);
+            }
+            FilterStrategy::MinimumSum => {
+                let mut best_sum = u64::MAX;
+                for filter_type in FILTER_TYPES {
+                    candidate.clear();
+                    filter::filter_row(
+                        filter_type,
+                        bytes_per_pixel,
+                        &previous,
+                        current,
+                        &mut candidate,
+                    );
+
+                    // Filtered bytes are treated as signed so that small negative residuals count as small.
+                    let sum = candidate[1..]
+                        .iter()
+                        .map(|&byte| (byte as i8).unsigned_abs() as u64)
+                        .sum();
+
+                    if sum < best_sum {
+                        best_sum = sum;
+                        std::mem::swap(&mut best, &mut candidate);
+                    }
+                }
+                output.extend_from_slice(&best);
+            }
+            FilterStrategy::BruteForce => {
+                let mut best_size = usize::MAX;
+                for filter_type in FILTER_TYPES {
+                    candidate.clear();
+                    filter::filter_row(
+                        filter_type,
+                        bytes_per_pixel,
+                        &previous,
+                        current,
+                        &mut candidate,
+                    );
+
+                    let size = deflate::compress_raw(&candidate, options.compression_level).len();
+                    if size < best_size {
+                        best_size = size;
+                        std::mem::swap(&mut best, &mut candidate);
+                    }
+                }
+                output.extend_from_slice(&best);
+            }
+        }
+
+        previous.copy_from_slice(current);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::parser::Parser;
+
+    fn header(
+        color_type: ColorType,
+        bit_depth: u8,
+        interlace_method: InterlaceMethod,
+    ) -> HeaderInfo {
+        return HeaderInfo {
+            width: 12,
+            height: 11,
+            bit_depth,
+            color_type,
+            compression_method: CompressionMethod::Deflate,
+            filter_method: FilterMethod::Adaptive,
+            interlace_method,
+        };
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let strategies = [
+            FilterStrategy::Fixed(FilterType::Paeth),
+            FilterStrategy::MinimumSum,
+            FilterStrategy::BruteForce,
+        ];
+
+        for (color_type, bit_depth) in [
+            (ColorType::Grayscale, 2),
+            (ColorType::TrueColor, 8),
+            (ColorType::TrueColorAlpha, 16),
+        ] {
+            for interlace_method in [InterlaceMethod::None, InterlaceMethod::Adam7] {
+                let header = header(color_type, bit_depth, interlace_method);
+                let pixels: Vec<u8> = (0..header.stride() * header.height as usize)
+                    .map(|i| (i * 7 % 251) as u8)
+                    .collect();
+
+                for filter_strategy in strategies {
+                    let options = EncoderOptions {
+                        filter_strategy,
+                        max_chunk_size: 64,
+                        ..Default::default()
+                    };
+
+                    let mut output = Vec::new();
+                    encode(&mut output, header.clone(), None, &pixels, &options).unwrap();
+
+                    let info = Parser::parse(&mut Cursor::new(&output)).unwrap();
+                    assert_eq!(info.decode().unwrap().pixels, pixels);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_animation_round_trip() {
+        let (width, height) = (8, 6);
+        // A dot moving over an opaque background, followed by a fully transparent frame.
+        let spinner: Vec<Vec<u8>> = (0..5)
+            .map(|step| {
+                (0..width * height)
+                    .flat_map(|i| match (step, i == 2 * width + step + 2) {
+                        (4, _) => [0, 0, 0, 0],
+                        (_, true) => [255, 0, 0, 255],
+                        (_, false) => [40, 40, 40, 255],
+                    })
+                    .collect()
+            })
+            .collect();
+        let frames: Vec<AnimationFrame> = spinner
+            .iter()
+            .map(|pixels| AnimationFrame {
+                pixels,
+                delay_numerator: 1,
+                delay_denominator: 12,
+            })
+            .collect();
+
+        let mut output = Vec::new();
+        encode_animation(&mut output, width, height, &frames, 0, &Default::default()).unwrap();
+
+        let info = Parser::parse(&mut Cursor::new(&output)).unwrap();
+        let animation = info.frames().unwrap();
+        assert!(animation.is_default_image_animated);
+        assert_eq!(animation.frames[1].control.width, 2);
+        assert_eq!(animation.frames[1].control.height, 1);
+        assert_eq!(animation.frames[1].control.blend_op, BlendOp::Over);
+        assert_eq!(animation.frames[4].control.blend_op, BlendOp::Source);
+
+        let rendered = info.render_frames().unwrap();
+        assert_eq!(rendered.len(), spinner.len());
+        for (rendered, pixels) in rendered.iter().zip(&spinner) {
+            assert_eq!(&rendered.pixels, pixels);
+            assert_eq!(rendered.delay, 1.0 / 12.0);
+        }
+    }
+
+    #[test]
+    fn test_changed_region() {
+        let base = [0u8; 4 * 4 * 3];
+        let mut current = base;
+        current[(4 + 1) * 4] = 1;
+        current[(2 * 4 + 2) * 4 + 3] = 1;
+
+        assert_eq!(
+            changed_region(&base, &current, 4, 3),
+            Region {
+                x: 1,
+                y: 1,
+                width: 2,
+                height: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_encode_invalid_pixel_length() {
+        let header = header(ColorType::TrueColor, 8, InterlaceMethod::None);
+        assert!(matches!(
+            encode_image_data(&header, &[0; 3], &EncoderOptions::default()),
+            Err(WriterError::InvalidImageDataLength)
+        ));
+    }
+}
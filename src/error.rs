@@ -17,6 +17,12 @@ pub enum ParserError {
     NonConsecutiveData,
     MissingNullTerminator,
     InvalidStringLength,
+    InvalidCompressedData,
+    InvalidFilterType(u8),
+    InvalidImageDataLength,
+    DecompressedSizeLimitExceeded,
+    InvalidTextEncoding,
+    InvalidSequenceNumber(u32),
 }
 
 impl Display for ParserError {
@@ -58,6 +64,61 @@ impl Display for ParserError {
                 "Chould not be able to find {} chunk which is required",
                 str::from_utf8(&u32::to_be_bytes(*chunk_id)).unwrap()
             ),
+            ParserError::InvalidCompressedData => write!(f, "Compressed data stream is malformed"),
+            ParserError::InvalidFilterType(filter_type) => {
+                write!(f, "Scanline has invalid filter type {}", filter_type)
+            }
+            ParserError::InvalidImageDataLength => {
+                write!(f, "Image data is too short for the image dimensions")
+            }
+            ParserError::DecompressedSizeLimitExceeded => {
+                write!(f, "Decompressed data exceeds the size limit")
+            }
+            ParserError::InvalidTextEncoding => write!(f, "Text is not valid UTF-8"),
+            ParserError::InvalidSequenceNumber(chunk_id) => write!(
+                f,
+                "{} chunk has out of order sequence number",
+                str::from_utf8(&u32::to_be_bytes(*chunk_id)).unwrap()
+            ),
+        }
+    }
+}
+
+/// Represents the errors related to the writer.
+#[derive(Debug)]
+pub enum WriterError {
+    IOError(Error),
+    InvalidChunkLength(u32),
+    InvalidFieldValue,
+    InvalidStringLength,
+    MissingRequiredChunk(u32),
+    InvalidImageDataLength,
+}
+
+impl Display for WriterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            WriterError::IOError(e) => write!(f, "IO error: {}", e),
+            WriterError::InvalidChunkLength(chunk_id) => write!(
+                f,
+                "{} chunk has invalid chunk length",
+                str::from_utf8(&u32::to_be_bytes(*chunk_id)).unwrap()
+            ),
+            WriterError::InvalidFieldValue => write!(f, "A field contains out of range value"),
+            WriterError::InvalidStringLength => {
+                write!(
+                    f,
+                    "Character strings like keyword/name must have a length between 1-79 inclusive"
+                )
+            }
+            WriterError::MissingRequiredChunk(chunk_id) => write!(
+                f,
+                "Chould not be able to find {} chunk which is required",
+                str::from_utf8(&u32::to_be_bytes(*chunk_id)).unwrap()
+            ),
+            WriterError::InvalidImageDataLength => {
+                write!(f, "Pixel data does not match the image dimensions")
+            }
         }
     }
 }
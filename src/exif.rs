@@ -0,0 +1,560 @@
+use crate::error::ParserError;
+
+/// The tags of the fields decoded into typed values.
+mod tags {
+    pub const MAKE: u16 = 0x010f;
+    pub const MODEL: u16 = 0x0110;
+    pub const ORIENTATION: u16 = 0x0112;
+    pub const EXIF_IFD_POINTER: u16 = 0x8769;
+    pub const GPS_IFD_POINTER: u16 = 0x8825;
+    pub const DATE_TIME_ORIGINAL: u16 = 0x9003;
+    pub const GPS_LATITUDE_REF: u16 = 0x0001;
+    pub const GPS_LATITUDE: u16 = 0x0002;
+    pub const GPS_LONGITUDE_REF: u16 = 0x0003;
+    pub const GPS_LONGITUDE: u16 = 0x0004;
+    pub const GPS_ALTITUDE_REF: u16 = 0x0005;
+    pub const GPS_ALTITUDE: u16 = 0x0006;
+}
+
+/// Describes the byte order of the multi-byte values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// The least significant byte comes first, marked with `II`.
+    LittleEndian,
+    /// The most significant byte comes first, marked with `MM`.
+    BigEndian,
+}
+
+/// Describes the image file directory that a field belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ifd {
+    /// The primary image directory.
+    Primary,
+    /// The EXIF private directory.
+    Exif,
+    /// The GPS directory.
+    Gps,
+}
+
+/// Describes the transformation that has to be applied to display the image upright.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// The image is displayed as it is.
+    Normal,
+    /// The image is mirrored horizontally.
+    FlipHorizontal,
+    /// The image is rotated by 180 degrees.
+    Rotate180,
+    /// The image is mirrored vertically.
+    FlipVertical,
+    /// The image is mirrored along its top-left to bottom-right diagonal.
+    Transpose,
+    /// The image is rotated by 90 degrees clockwise.
+    Rotate90,
+    /// The image is mirrored along its top-right to bottom-left diagonal.
+    Transverse,
+    /// The image is rotated by 270 degrees clockwise.
+    Rotate270,
+}
+
+/// Describes the value of a field according to its type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExifValue {
+    /// Unsigned 8-bit integers.
+    Byte(Vec<u8>),
+    /// A text, without its null-terminator.
+    Ascii(String),
+    /// Unsigned 16-bit integers.
+    Short(Vec<u16>),
+    /// Unsigned 32-bit integers.
+    Long(Vec<u32>),
+    /// Unsigned fractions as numerator and denominator pairs.
+    Rational(Vec<(u32, u32)>),
+    /// Signed 8-bit integers.
+    SignedByte(Vec<i8>),
+    /// Bytes whose interpretation depends on the field.
+    Undefined(Vec<u8>),
+    /// Signed 16-bit integers.
+    SignedShort(Vec<i16>),
+    /// Signed 32-bit integers.
+    SignedLong(Vec<i32>),
+    /// Signed fractions as numerator and denominator pairs.
+    SignedRational(Vec<(i32, i32)>),
+    /// 32-bit floating points.
+    Float(Vec<f32>),
+    /// 64-bit floating points.
+    Double(Vec<f64>),
+}
+
+/// Represents a field of an image file directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExifField {
+    /// The directory that contains the field.
+    pub ifd: Ifd,
+    /// The tag that identifies the field.
+    pub tag: u16,
+    /// The value of the field.
+    pub value: ExifValue,
+}
+
+/// Represents the location where the image is taken.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsCoordinates {
+    /// The latitude in degrees, negative for the southern hemisphere.
+    pub latitude: f64,
+    /// The longitude in degrees, negative for the western hemisphere.
+    pub longitude: f64,
+    /// The altitude in meters, negative for below the sea level.
+    pub altitude: Option<f64>,
+}
+
+/// Represents the metadata read from an EXIF profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExifMetadata {
+    /// The byte order of the profile.
+    pub byte_order: ByteOrder,
+    /// Every field of the primary, EXIF and GPS directories.
+    pub fields: Vec<ExifField>,
+    /// The orientation of the image.
+    pub orientation: Option<Orientation>,
+    /// The manufacturer of the recording equipment.
+    pub make: Option<String>,
+    /// The model of the recording equipment.
+    pub model: Option<String>,
+    /// The date and time when the original image is generated, in the `YYYY:MM:DD HH:MM:SS` format.
+    pub date_time_original: Option<String>,
+    /// The location where the image is taken.
+    pub gps: Option<GpsCoordinates>,
+}
+
+impl ExifMetadata {
+    /// Returns the field with the provided tag in the provided directory.
+    pub fn field(&self, ifd: Ifd, tag: u16) -> Option<&ExifField> {
+        return self
+            .fields
+            .iter()
+            .find(|field| field.ifd == ifd && field.tag == tag);
+    }
+}
+
+/// Reads the metadata out of a TIFF structured EXIF profile.
+///
+/// # Arguments
+/// * `data` - The profile starting with the TIFF header.
+///
+/// # Returns
+/// * `ExifMetadata` - The fields of the primary, EXIF and GPS directories with the common ones decoded.
+///   Malformed entries and unreadable sub-directories are left out, and only an invalid TIFF header or primary
+///   directory fails the read.
+pub fn read(data: &[u8]) -> Result<ExifMetadata, ParserError> {
+    let byte_order = match data.get(0..4) {
+        Some([0x49, 0x49, 0x2a, 0x00]) => ByteOrder::LittleEndian,
+        Some([0x4d, 0x4d, 0x00, 0x2a]) => ByteOrder::BigEndian,
+        _ => return Err(ParserError::InvalidFieldValue),
+    };
+
+    let reader = Reader { data, byte_order };
+    let mut fields = Vec::new();
+
+    let offset = reader.u32(4)?;
+    reader.read_ifd(Ifd::Primary, offset, &mut fields)?;
+
+    // The sub-directories are only followed from the primary directory, so that cyclic pointers cannot loop.
+    for (pointer, ifd) in [
+        (tags::EXIF_IFD_POINTER, Ifd::Exif),
+        (tags::GPS_IFD_POINTER, Ifd::Gps),
+    ] {
+        let offset = fields
+            .iter()
+            .find(|field| field.ifd == Ifd::Primary && field.tag == pointer)
+            .and_then(|field| first_integer(&field.value));
+
+        // A sub-directory that cannot be read is left out, like a malformed entry.
+        if let Some(offset) = offset {
+            let _ = reader.read_ifd(ifd, offset, &mut fields);
+        }
+    }
+
+    let mut metadata = ExifMetadata {
+        byte_order,
+        fields,
+        orientation: None,
+        make: None,
+        model: None,
+        date_time_original: None,
+        gps: None,
+    };
+
+    metadata.orientation = metadata
+        .field(Ifd::Primary, tags::ORIENTATION)
+        .and_then(|field| first_integer(&field.value))
+        .and_then(|value| match value {
+            1 => Some(Orientation::Normal),
+            2 => Some(Orientation::FlipHorizontal),
+            3 => Some(Orientation::Rotate180),
+            4 => Some(Orientation::FlipVertical),
+            5 => Some(Orientation::Transpose),
+            6 => Some(Orientation::Rotate90),
+            7 => Some(Orientation::Transverse),
+            8 => Some(Orientation::Rotate270),
+            _ => None,
+        });
+    metadata.make = ascii(&metadata, Ifd::Primary, tags::MAKE);
+    metadata.model = ascii(&metadata, Ifd::Primary, tags::MODEL);
+    metadata.date_time_original = ascii(&metadata, Ifd::Exif, tags::DATE_TIME_ORIGINAL);
+    metadata.gps = gps_coordinates(&metadata);
+
+    return Ok(metadata);
+}
+
+/// Reads values from the profile in its byte order, failing on out of range offsets.
+struct Reader<'a> {
+    data: &'a [u8],
+    byte_order: ByteOrder,
+}
+
+impl Reader<'_> {
+    fn bytes(&self, offset: usize, length: usize) -> Result<&[u8], ParserError> {
+        return match offset.checked_add(length) {
+            Some(end) if end <= self.data.len() => Ok(&self.data[offset..end]),
+            _ => Err(ParserError::InvalidFieldValue),
+        };
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, ParserError> {
+        let bytes: [u8; 2] = self.bytes(offset, 2)?.try_into().unwrap();
+        return Ok(match self.byte_order {
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+        });
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, ParserError> {
+        let bytes: [u8; 4] = self.bytes(offset, 4)?.try_into().unwrap();
+        return Ok(match self.byte_order {
+            ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+        });
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64, ParserError> {
+        let bytes: [u8; 8] = self.bytes(offset, 8)?.try_into().unwrap();
+        return Ok(match self.byte_order {
+            ByteOrder::LittleEndian => u64::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u64::from_be_bytes(bytes),
+        });
+    }
+
+    fn read_ifd(
+        &self,
+        ifd: Ifd,
+        offset: u32,
+        fields: &mut Vec<ExifField>,
+    ) -> Result<(), ParserError> {
+        let offset = offset as usize;
+        let count = self.u16(offset)? as usize;
+
+        for index in 0..count {
+            // A malformed entry is skipped, so that the rest of the profile stays readable.
+            if let Ok(Some(field)) = self.read_entry(ifd, offset + 2 + index * 12) {
+                fields.push(field);
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn read_entry(&self, ifd: Ifd, entry: usize) -> Result<Option<ExifField>, ParserError> {
+        let tag = self.u16(entry)?;
+        let field_type = self.u16(entry + 2)?;
+        let count = self.u32(entry + 4)? as usize;
+
+        // Fields of unknown types cannot be sized, so they are skipped as the specification requires.
+        let Some(size) = type_size(field_type) else {
+            return Ok(None);
+        };
+
+        let length = match count.checked_mul(size) {
+            Some(length) => length,
+            None => return Err(ParserError::InvalidFieldValue),
+        };
+
+        // Values that fit into four bytes are stored in place of their offset.
+        let value_offset = match length {
+            0..=4 => entry + 8,
+            _ => self.u32(entry + 8)? as usize,
+        };
+
+        self.bytes(value_offset, length)?;
+        let value = self.read_value(field_type, value_offset, count)?;
+
+        return Ok(Some(ExifField { ifd, tag, value }));
+    }
+
+    fn read_value(
+        &self,
+        field_type: u16,
+        offset: usize,
+        count: usize,
+    ) -> Result<ExifValue, ParserError> {
+        let size = type_size(field_type).unwrap();
+        let offsets = (0..count).map(|index| offset + index * size);
+
+        let value =
+            match field_type {
+                1 => ExifValue::Byte(self.bytes(offset, count)?.to_vec()),
+                2 => {
+                    let bytes = self.bytes(offset, count)?;
+                    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+                    ExifValue::Ascii(String::from_utf8_lossy(&bytes[..end]).into_owned())
+                }
+                3 => ExifValue::Short(offsets.map(|o| self.u16(o)).collect::<Result<_, _>>()?),
+                4 => ExifValue::Long(offsets.map(|o| self.u32(o)).collect::<Result<_, _>>()?),
+                5 => ExifValue::Rational(
+                    offsets
+                        .map(|o| Ok((self.u32(o)?, self.u32(o + 4)?)))
+                        .collect::<Result<_, ParserError>>()?,
+                ),
+                6 => ExifValue::SignedByte(
+                    self.bytes(offset, count)?
+                        .iter()
+                        .map(|&b| b as i8)
+                        .collect(),
+                ),
+                7 => ExifValue::Undefined(self.bytes(offset, count)?.to_vec()),
+                8 => ExifValue::SignedShort(
+                    offsets
+                        .map(|o| Ok(self.u16(o)? as i16))
+                        .collect::<Result<_, ParserError>>()?,
+                ),
+                9 => ExifValue::SignedLong(
+                    offsets
+                        .map(|o| Ok(self.u32(o)? as i32))
+                        .collect::<Result<_, ParserError>>()?,
+                ),
+                10 => ExifValue::SignedRational(
+                    offsets
+                        .map(|o| Ok((self.u32(o)? as i32, self.u32(o + 4)? as i32)))
+                        .collect::<Result<_, ParserError>>()?,
+                ),
+                11 => ExifValue::Float(
+                    offsets
+                        .map(|o| Ok(f32::from_bits(self.u32(o)?)))
+                        .collect::<Result<_, ParserError>>()?,
+                ),
+                _ => ExifValue::Double(
+                    offsets
+                        .map(|o| Ok(f64::from_bits(self.u64(o)?)))
+                        .collect::<Result<_, ParserError>>()?,
+                ),
+            };
+
+        return Ok(value);
+    }
+}
+
+/// Returns the number of bytes of a single value of the provided field type.
+fn type_size(field_type: u16) -> Option<usize> {
+    return match field_type {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    };
+}
+
+/// Returns the first value of an unsigned integer field.
+fn first_integer(value: &ExifValue) -> Option<u32> {
+    return match value {
+        ExifValue::Byte(values) => values.first().map(|&v| v as u32),
+        ExifValue::Short(values) => values.first().map(|&v| v as u32),
+        ExifValue::Long(values) => values.first().copied(),
+        _ => None,
+    };
+}
+
+fn ascii(metadata: &ExifMetadata, ifd: Ifd, tag: u16) -> Option<String> {
+    return match metadata.field(ifd, tag) {
+        Some(ExifField {
+            value: ExifValue::Ascii(text),
+            ..
+        }) => Some(text.trim_end().to_string()),
+        _ => None,
+    };
+}
+
+fn rationals(metadata: &ExifMetadata, tag: u16) -> Option<Vec<f64>> {
+    return match metadata.field(Ifd::Gps, tag) {
+        Some(ExifField {
+            value: ExifValue::Rational(values),
+            ..
+        }) if values.iter().all(|&(_, denominator)| denominator != 0) => Some(
+            values
+                .iter()
+                .map(|&(numerator, denominator)| numerator as f64 / denominator as f64)
+                .collect(),
+        ),
+        _ => None,
+    };
+}
+
+/// Converts the degrees, minutes and seconds of a coordinate and its reference into signed degrees.
+fn coordinate(
+    metadata: &ExifMetadata,
+    tag: u16,
+    reference_tag: u16,
+    negative: &str,
+) -> Option<f64> {
+    let values = rationals(metadata, tag)?;
+    if values.len() != 3 {
+        return None;
+    }
+
+    let degrees = values[0] + values[1] / 60.0 + values[2] / 3600.0;
+    return match ascii(metadata, Ifd::Gps, reference_tag) {
+        Some(reference) if reference == negative => Some(-degrees),
+        _ => Some(degrees),
+    };
+}
+
+fn gps_coordinates(metadata: &ExifMetadata) -> Option<GpsCoordinates> {
+    let latitude = coordinate(metadata, tags::GPS_LATITUDE, tags::GPS_LATITUDE_REF, "S")?;
+    let longitude = coordinate(metadata, tags::GPS_LONGITUDE, tags::GPS_LONGITUDE_REF, "W")?;
+
+    let altitude = rationals(metadata, tags::GPS_ALTITUDE)
+        .and_then(|values| values.first().copied())
+        .map(
+            |altitude| match metadata.field(Ifd::Gps, tags::GPS_ALTITUDE_REF) {
+                Some(ExifField {
+                    value: ExifValue::Byte(reference),
+                    ..
+                }) if reference.first() == Some(&1) => -altitude,
+                _ => altitude,
+            },
+        );
+
+    return Some(GpsCoordinates {
+        latitude,
+        longitude,
+        altitude,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the size of a directory with the provided number of entries and its next directory offset.
+    const fn ifd_size(entry_count: u32) -> u32 {
+        return 2 + entry_count * 12 + 4;
+    }
+
+    const PRIMARY_IFD: u32 = 8;
+    const EXIF_IFD: u32 = PRIMARY_IFD + ifd_size(4);
+    const GPS_IFD: u32 = EXIF_IFD + ifd_size(1);
+    const COORDINATES: u32 = GPS_IFD + ifd_size(4);
+    const DATE_TIME_ORIGINAL: u32 = COORDINATES + 3 * 8;
+
+    /// Builds a little-endian profile with Make, Orientation, an EXIF directory with DateTimeOriginal and a GPS directory.
+    ///
+    /// The longitude shares the three rationals of the latitude, of which it reads the provided number of values.
+    fn profile(longitude_count: u32, date_time_offset: u32) -> Vec<u8> {
+        let mut data = vec![0x49, 0x49, 0x2a, 0x00];
+        data.extend_from_slice(&PRIMARY_IFD.to_le_bytes());
+        let entry = |data: &mut Vec<u8>, tag: u16, field_type: u16, count: u32, value: u32| {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&field_type.to_le_bytes());
+            data.extend_from_slice(&count.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+        };
+
+        data.extend_from_slice(&4u16.to_le_bytes());
+        entry(&mut data, tags::MAKE, 2, 4, u32::from_le_bytes(*b"ACM\0"));
+        entry(&mut data, tags::ORIENTATION, 3, 1, 6);
+        entry(&mut data, tags::EXIF_IFD_POINTER, 4, 1, EXIF_IFD);
+        entry(&mut data, tags::GPS_IFD_POINTER, 4, 1, GPS_IFD);
+        data.extend_from_slice(&0u32.to_le_bytes());
+
+        data.extend_from_slice(&1u16.to_le_bytes());
+        entry(&mut data, tags::DATE_TIME_ORIGINAL, 2, 20, date_time_offset);
+        data.extend_from_slice(&0u32.to_le_bytes());
+
+        data.extend_from_slice(&4u16.to_le_bytes());
+        entry(
+            &mut data,
+            tags::GPS_LATITUDE_REF,
+            2,
+            2,
+            u32::from_le_bytes(*b"N\0\0\0"),
+        );
+        entry(&mut data, tags::GPS_LATITUDE, 5, 3, COORDINATES);
+        entry(
+            &mut data,
+            tags::GPS_LONGITUDE_REF,
+            2,
+            2,
+            u32::from_le_bytes(*b"W\0\0\0"),
+        );
+        entry(
+            &mut data,
+            tags::GPS_LONGITUDE,
+            5,
+            longitude_count,
+            COORDINATES,
+        );
+        data.extend_from_slice(&0u32.to_le_bytes());
+
+        assert_eq!(data.len(), COORDINATES as usize);
+        for value in [41u32, 1, 30, 1, 0, 1] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        assert_eq!(data.len(), DATE_TIME_ORIGINAL as usize);
+        data.extend_from_slice(b"2024:05:01 10:20:30\0");
+
+        return data;
+    }
+
+    #[test]
+    fn test_read_profile() {
+        let metadata = read(&profile(1, DATE_TIME_ORIGINAL)).unwrap();
+
+        assert_eq!(metadata.byte_order, ByteOrder::LittleEndian);
+        assert_eq!(metadata.make.as_deref(), Some("ACM"));
+        assert_eq!(metadata.orientation, Some(Orientation::Rotate90));
+        assert_eq!(
+            metadata.date_time_original.as_deref(),
+            Some("2024:05:01 10:20:30")
+        );
+        // The longitude has a single value, so the coordinates are incomplete.
+        assert_eq!(metadata.gps, None);
+
+        // Reading all three values of the latitude as the longitude makes the coordinates complete.
+        let gps = read(&profile(3, DATE_TIME_ORIGINAL)).unwrap().gps.unwrap();
+        assert_eq!(gps.latitude, 41.5);
+        assert_eq!(gps.longitude, -41.5);
+        assert_eq!(gps.altitude, None);
+    }
+
+    #[test]
+    fn test_read_malformed_entry() {
+        let metadata = read(&profile(3, u32::MAX)).unwrap();
+
+        assert_eq!(metadata.date_time_original, None);
+        assert_eq!(metadata.field(Ifd::Exif, tags::DATE_TIME_ORIGINAL), None);
+        assert_eq!(metadata.make.as_deref(), Some("ACM"));
+        assert!(metadata.gps.is_some());
+    }
+
+    #[test]
+    fn test_read_out_of_range_offset() {
+        let mut data = profile(1, DATE_TIME_ORIGINAL);
+        data[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(read(&data), Err(ParserError::InvalidFieldValue)));
+        assert!(matches!(
+            read(b"XX*\0"),
+            Err(ParserError::InvalidFieldValue)
+        ));
+    }
+}
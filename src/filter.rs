@@ -0,0 +1,283 @@
+use crate::error::ParserError;
+use crate::interlace;
+use crate::spec::{FilterType, HeaderInfo, InterlaceMethod};
+
+/// Reconstructs the scanlines of an image from its decompressed data, de-interlacing it if necessary.
+///
+/// # Arguments
+/// * `header` - The header describing the image layout.
+/// * `data` - The decompressed data, where each scanline starts with its filter type byte.
+///
+/// # Returns
+/// * `Vec<u8>` - The unfiltered scanlines packed one after another without filter type bytes.
+pub fn unfilter(header: &HeaderInfo, data: &[u8]) -> Result<Vec<u8>, ParserError> {
+    return match header.interlace_method {
+        InterlaceMethod::None => {
+            unfilter_scanlines(data, header.width, header.height, header.bits_per_pixel())
+        }
+        InterlaceMethod::Adam7 => interlace::deinterlace(header, data),
+    };
+}
+
+/// Returns the number of bytes in a scanline of the provided width, excluding the filter type byte.
+pub(crate) fn stride(width: u32, bits_per_pixel: usize) -> usize {
+    return (width as usize * bits_per_pixel).div_ceil(8);
+}
+
+/// Returns the number of bytes of `height` filtered scanlines of `width` pixels including their filter type bytes,
+/// or `None` if the size does not fit in memory.
+pub(crate) fn filtered_size(width: u32, height: u32, bits_per_pixel: usize) -> Option<usize> {
+    return stride(width, bits_per_pixel)
+        .checked_add(1)?
+        .checked_mul(height as usize);
+}
+
+/// Returns the number of decompressed image data bytes described by the header, including the filter type bytes
+/// of every scanline of every Adam7 pass, or `None` if the size does not fit in memory.
+pub(crate) fn image_data_size(header: &HeaderInfo) -> Option<usize> {
+    let bits_per_pixel = header.bits_per_pixel();
+
+    return match header.interlace_method {
+        InterlaceMethod::None => filtered_size(header.width, header.height, bits_per_pixel),
+        InterlaceMethod::Adam7 => {
+            let mut size = 0usize;
+            for pass in 0..interlace::ADAM7_PASSES.len() {
+                let (pass_width, pass_height) =
+                    interlace::pass_size(pass, header.width, header.height);
+
+                // Empty passes are not transmitted at all, not even their filter type bytes.
+                if pass_width == 0 || pass_height == 0 {
+                    continue;
+                }
+
+                size = size.checked_add(filtered_size(pass_width, pass_height, bits_per_pixel)?)?;
+            }
+            Some(size)
+        }
+    };
+}
+
+/// Reconstructs `height` scanlines of `width` pixels from the start of the provided data.
+pub(crate) fn unfilter_scanlines(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    bits_per_pixel: usize,
+) -> Result<Vec<u8>, ParserError> {
+    let stride = stride(width, bits_per_pixel);
+    let bytes_per_pixel = bits_per_pixel.div_ceil(8);
+
+    match filtered_size(width, height, bits_per_pixel) {
+        Some(size) if data.len() >= size => {}
+        _ => return Err(ParserError::InvalidImageDataLength),
+    }
+
+    let mut output = vec![0u8; stride * height as usize];
+    let previous_row = vec![0u8; stride];
+
+    for (y, filtered) in data
+        .chunks_exact(stride + 1)
+        .take(height as usize)
+        .enumerate()
+    {
+        let (before, current) = output.split_at_mut(y * stride);
+        let previous = match y {
+            0 => &previous_row[..],
+            _ => &before[(y - 1) * stride..],
+        };
+
+        unfilter_scanline(filtered, previous, bytes_per_pixel, &mut current[..stride])?;
+    }
+
+    return Ok(output);
+}
+
+/// Reconstructs a single scanline from its filtered form.
+///
+/// # Arguments
+/// * `filtered` - The filtered scanline, starting with its filter type byte.
+/// * `previous` - The reconstructed previous scanline, or zeros for the first scanline of an image or pass.
+/// * `bytes_per_pixel` - The number of bytes per complete pixel, rounded up to one.
+/// * `current` - The buffer receiving the reconstructed scanline, which is as long as `previous`.
+pub(crate) fn unfilter_scanline(
+    filtered: &[u8],
+    previous: &[u8],
+    bytes_per_pixel: usize,
+    current: &mut [u8],
+) -> Result<(), ParserError> {
+    let filter_type = match filtered[0] {
+        0 => FilterType::None,
+        1 => FilterType::Sub,
+        2 => FilterType::Up,
+        3 => FilterType::Average,
+        4 => FilterType::Paeth,
+        value => return Err(ParserError::InvalidFilterType(value)),
+    };
+
+    current.copy_from_slice(&filtered[1..]);
+    unfilter_row(filter_type, bytes_per_pixel, previous, current);
+
+    return Ok(());
+}
+
+/// Reverses the provided filter type on a scanline in place, using the already reconstructed previous scanline.
+fn unfilter_row(
+    filter_type: FilterType,
+    bytes_per_pixel: usize,
+    previous: &[u8],
+    current: &mut [u8],
+) {
+    match filter_type {
+        FilterType::None => {}
+        FilterType::Sub => {
+            for i in bytes_per_pixel..current.len() {
+                current[i] = current[i].wrapping_add(current[i - bytes_per_pixel]);
+            }
+        }
+        FilterType::Up => {
+            for i in 0..current.len() {
+                current[i] = current[i].wrapping_add(previous[i]);
+            }
+        }
+        FilterType::Average => {
+            for i in 0..current.len() {
+                let left = match i >= bytes_per_pixel {
+                    true => current[i - bytes_per_pixel] as u16,
+                    false => 0,
+                };
+                let average = ((left + previous[i] as u16) / 2) as u8;
+                current[i] = current[i].wrapping_add(average);
+            }
+        }
+        FilterType::Paeth => {
+            for i in 0..current.len() {
+                let (left, upper_left) = match i >= bytes_per_pixel {
+                    true => (current[i - bytes_per_pixel], previous[i - bytes_per_pixel]),
+                    false => (0, 0),
+                };
+                current[i] = current[i].wrapping_add(paeth(left, previous[i], upper_left));
+            }
+        }
+    }
+}
+
+/// Applies the provided filter type to a scanline, appending the filter type byte and the filtered bytes to the output.
+pub(crate) fn filter_row(
+    filter_type: FilterType,
+    bytes_per_pixel: usize,
+    previous: &[u8],
+    current: &[u8],
+    output: &mut Vec<u8>,
+) {
+    output.push(match filter_type {
+        FilterType::None => 0,
+        FilterType::Sub => 1,
+        FilterType::Up => 2,
+        FilterType::Average => 3,
+        FilterType::Paeth => 4,
+    });
+
+    for i in 0..current.len() {
+        let (left, upper_left) = match i >= bytes_per_pixel {
+            true => (current[i - bytes_per_pixel], previous[i - bytes_per_pixel]),
+            false => (0, 0),
+        };
+        let above = previous[i];
+
+        let prediction = match filter_type {
+            FilterType::None => 0,
+            FilterType::Sub => left,
+            FilterType::Up => above,
+            FilterType::Average => ((left as u16 + above as u16) / 2) as u8,
+            FilterType::Paeth => paeth(left, above, upper_left),
+        };
+
+        output.push(current[i].wrapping_sub(prediction));
+    }
+}
+
+/// Predicts a byte from its left, above and upper left neighbours as defined by the PNG specification.
+pub(crate) fn paeth(left: u8, above: u8, upper_left: u8) -> u8 {
+    let estimate = left as i16 + above as i16 - upper_left as i16;
+    let distance_left = (estimate - left as i16).abs();
+    let distance_above = (estimate - above as i16).abs();
+    let distance_upper_left = (estimate - upper_left as i16).abs();
+
+    if distance_left <= distance_above && distance_left <= distance_upper_left {
+        return left;
+    }
+
+    if distance_above <= distance_upper_left {
+        return above;
+    }
+
+    return upper_left;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unfilter_all_filter_types() {
+        // Two bytes per pixel, two pixels per scanline.
+        let data = [
+            0, 10, 20, 30, 40, //
+            1, 10, 20, 5, 5, //
+            2, 1, 1, 1, 1, //
+            3, 2, 2, 2, 2, //
+            4, 1, 1, 1, 1,
+        ];
+        let output = unfilter_scanlines(&data, 2, 5, 16).unwrap();
+        assert_eq!(
+            output,
+            [
+                10, 20, 30, 40, //
+                10, 20, 15, 25, //
+                11, 21, 16, 26, //
+                7, 12, 13, 21, //
+                8, 13, 14, 22,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unfilter_oversized_dimensions() {
+        let width = i32::MAX as u32;
+        assert_eq!(filtered_size(width, width, 64), None);
+        assert!(matches!(
+            unfilter_scanlines(&[0; 16], width, width, 64),
+            Err(ParserError::InvalidImageDataLength)
+        ));
+    }
+
+    #[test]
+    fn test_filter_row_reverses_unfilter() {
+        let previous = [3, 200, 17, 90, 255, 0];
+        let current = [10, 20, 250, 40, 7, 128];
+
+        for filter_type in [
+            FilterType::None,
+            FilterType::Sub,
+            FilterType::Up,
+            FilterType::Average,
+            FilterType::Paeth,
+        ] {
+            let mut filtered = Vec::new();
+            filter_row(filter_type, 3, &previous, &current, &mut filtered);
+
+            let mut row = filtered[1..].to_vec();
+            unfilter_row(filter_type, 3, &previous, &mut row);
+            assert_eq!(row, current);
+        }
+    }
+
+    #[test]
+    fn test_unfilter_invalid_filter_type() {
+        let data = [5, 0, 0];
+        assert!(matches!(
+            unfilter_scanlines(&data, 2, 1, 8),
+            Err(ParserError::InvalidFilterType(5))
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+use std::any::Any;
+
+use crate::error::ParserError;
+use crate::spec::*;
+
+/// Represents the state of the parser at the time a custom chunk is handled.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkContext<'a> {
+    /// The header of the image.
+    pub header: &'a HeaderInfo,
+    /// Whether the `PLTE` chunk has been seen.
+    pub has_palette: bool,
+    /// Whether an `IDAT` chunk has been seen.
+    pub has_image_data: bool,
+}
+
+/// Describes a handler that turns the data of a chunk the parser does not recognize into a typed value.
+///
+/// Handlers are registered per chunk type through `ParserOptions::with_handler`, and the produced values are
+/// retrieved afterwards with `PngInfo::custom_chunk` and `PngInfo::custom_chunks`.
+pub trait ChunkHandler {
+    /// The type of the value produced for each chunk.
+    type Value: Any + Send + Sync;
+
+    /// Handles the data of a chunk.
+    ///
+    /// # Arguments
+    /// * `context` - The state of the parser when the chunk is encountered.
+    /// * `data` - The chunk data.
+    ///
+    /// # Returns
+    /// * `Result<Self::Value, ParserError>` - The value of the chunk, or an error that aborts the parsing.
+    fn handle(&self, context: &ChunkContext<'_>, data: &[u8]) -> Result<Self::Value, ParserError>;
+}
+
+/// Erases the value type of a chunk handler so that handlers of different types can be stored together.
+pub(crate) trait DynChunkHandler {
+    fn handle_dyn(
+        &self,
+        context: &ChunkContext<'_>,
+        data: &[u8],
+    ) -> Result<Box<dyn Any + Send + Sync>, ParserError>;
+}
+
+impl<Handler: ChunkHandler> DynChunkHandler for Handler {
+    fn handle_dyn(
+        &self,
+        context: &ChunkContext<'_>,
+        data: &[u8],
+    ) -> Result<Box<dyn Any + Send + Sync>, ParserError> {
+        return Ok(Box::new(self.handle(context, data)?));
+    }
+}
+
+impl PngInfo {
+    /// Returns the value produced for the first chunk of the provided type, if its handler produced a `Value`.
+    pub fn custom_chunk<Value: Any>(&self, chunk_type: [u8; 4]) -> Option<&Value> {
+        return self.custom_chunks::<Value>(chunk_type).next();
+    }
+
+    /// Returns an iterator over the values produced for the chunks of the provided type in stream order.
+    pub fn custom_chunks<Value: Any>(
+        &self,
+        chunk_type: [u8; 4],
+    ) -> impl Iterator<Item = &Value> + '_ {
+        return self
+            .custom_chunks
+            .iter()
+            .filter(move |chunk| chunk.chunk_type == chunk_type)
+            .filter_map(|chunk| chunk.value.downcast_ref::<Value>());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::crc32;
+    use crate::deflate::{self, CompressionLevel};
+    use crate::parser::{Parser, ParserOptions};
+    use crate::test_utils::chunk;
+    use crate::writer::Writer;
+
+    /// Represents the value of a private chunk that records the application that created the image.
+    #[derive(Debug, PartialEq)]
+    struct Creator {
+        name: String,
+        after_image_data: bool,
+    }
+
+    struct CreatorHandler;
+
+    impl ChunkHandler for CreatorHandler {
+        type Value = Creator;
+
+        fn handle(&self, context: &ChunkContext<'_>, data: &[u8]) -> Result<Creator, ParserError> {
+            let name =
+                String::from_utf8(data.to_vec()).map_err(|_| ParserError::InvalidTextEncoding)?;
+
+            return Ok(Creator {
+                name,
+                after_image_data: context.has_image_data,
+            });
+        }
+    }
+
+    fn png_with_creators() -> Vec<u8> {
+        let info = PngInfo::new(
+            HeaderInfo {
+                width: 1,
+                height: 1,
+                bit_depth: 8,
+                color_type: ColorType::Grayscale,
+                compression_method: CompressionMethod::Deflate,
+                filter_method: FilterMethod::Adaptive,
+                interlace_method: InterlaceMethod::None,
+            },
+            CompressedDataInfo {
+                chunk_count: 1,
+                chunk_lengths: Vec::new(),
+                data: deflate::compress(&[0, 0], CompressionLevel::Default),
+            },
+        );
+        let mut png = Vec::new();
+        Writer::write(&mut png, &info).unwrap();
+
+        // Places one chunk before the image data followed by an unknown chunk, and one after it right before the trailer.
+        let idat = 8 + 25;
+        let iend = png.len() - 12;
+        let mut bytes = png[..idat].to_vec();
+        bytes.extend(chunk(b"crEa", b"before"));
+        bytes.extend(chunk(b"prVt", &[1, 2, 3]));
+        bytes.extend_from_slice(&png[idat..iend]);
+        bytes.extend(chunk(b"crEa", b"after"));
+        bytes.extend_from_slice(&png[iend..]);
+        return bytes;
+    }
+
+    #[test]
+    fn test_custom_chunk_handler() {
+        let options = ParserOptions::new().with_handler(*b"crEa", CreatorHandler);
+        let png = png_with_creators();
+        let info = Parser::parse_with_options(&mut Cursor::new(&png), &options).unwrap();
+
+        assert_eq!(info.unknown_chunks.len(), 1);
+        let creators: Vec<&Creator> = info.custom_chunks::<Creator>(*b"crEa").collect();
+        assert_eq!(creators.len(), 2);
+        assert_eq!(creators[0].name, "before");
+        assert!(!creators[0].after_image_data);
+        assert_eq!(creators[1].name, "after");
+        assert!(creators[1].after_image_data);
+        assert!(info.custom_chunk::<String>(*b"crEa").is_none());
+
+        // The raw data, position and order are kept so that writing the image preserves the chunks.
+        let mut written = Vec::new();
+        Writer::write(&mut written, &info).unwrap();
+        assert_eq!(written, png);
+    }
+
+    #[test]
+    fn test_custom_chunk_handler_error() {
+        let options = ParserOptions::new().with_handler(*b"crEa", CreatorHandler);
+        let mut png = png_with_creators();
+        let position = png
+            .windows(6)
+            .position(|window| window == b"before")
+            .unwrap();
+        png[position] = 0xff;
+        let crc_position = position + 6;
+        let crc = crc32::compute(&png[position - 4..crc_position]);
+        png[crc_position..crc_position + 4].copy_from_slice(&crc.to_be_bytes());
+
+        assert!(matches!(
+            Parser::parse_with_options(&mut Cursor::new(&png), &options),
+            Err(ParserError::InvalidTextEncoding)
+        ));
+    }
+}
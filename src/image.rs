@@ -0,0 +1,312 @@
+use crate::convert;
+use crate::error::ParserError;
+use crate::filter;
+use crate::inflate;
+use crate::spec::{ColorType, HeaderInfo, PngInfo, TransparencyVariant};
+
+/// Represents the layout of decoded pixel data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageLayout {
+    /// The width in pixels.
+    pub width: u32,
+    /// The height in pixels.
+    pub height: u32,
+    /// The color type.
+    pub color_type: ColorType,
+    /// The number of samples per pixel.
+    pub channels: u8,
+    /// The number of bits per sample.
+    pub bit_depth: u8,
+}
+
+impl ImageLayout {
+    /// Creates the layout described by the provided header.
+    pub fn from_header(header: &HeaderInfo) -> Self {
+        return Self {
+            width: header.width,
+            height: header.height,
+            color_type: header.color_type,
+            channels: header.color_type.channels(),
+            bit_depth: header.bit_depth,
+        };
+    }
+
+    /// Returns the number of bits in each pixel.
+    pub fn bits_per_pixel(&self) -> usize {
+        return self.channels as usize * self.bit_depth as usize;
+    }
+
+    /// Returns the number of bytes in each row.
+    pub fn stride(&self) -> usize {
+        return filter::stride(self.width, self.bits_per_pixel());
+    }
+
+    /// Checks whether the channel count and bit depth match the color type.
+    pub fn is_valid(&self) -> bool {
+        return self.channels == self.color_type.channels()
+            && self.color_type.allows_bit_depth(self.bit_depth);
+    }
+}
+
+/// Describes the samples of a single pixel in the bit depth of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pixel {
+    /// For grayscale images, a single gray level value.
+    Grayscale(u16),
+    /// For true-color images, an RGB color value.
+    TrueColor(u16, u16, u16),
+    /// For indexed-color images, a palette index.
+    IndexedColor(u8),
+    /// For grayscale images with alpha channel, a gray level value followed by an alpha value.
+    GrayscaleAlpha(u16, u16),
+    /// For true-color images with alpha channel, an RGB color value followed by an alpha value.
+    TrueColorAlpha(u16, u16, u16, u16),
+}
+
+/// Represents a decoded image.
+#[derive(Debug, Clone)]
+pub struct Image {
+    /// The layout of the pixels.
+    pub layout: ImageLayout,
+    /// The palette colors for indexed-color images.
+    pub palette: Option<Vec<(u8, u8, u8)>>,
+    /// The transparency applied when converting to RGBA.
+    pub transparency: Option<TransparencyVariant>,
+    /// The pixel rows packed one after another, with samples smaller than a byte packed into bytes and 16-bit samples in big-endian order.
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Returns the pixel at the provided position, or `None` if it is outside of the image.
+    ///
+    /// As the fields are public, `None` is also returned if the layout does not describe a valid image or the
+    /// pixels are too short for it.
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<Pixel> {
+        if x >= self.layout.width || y >= self.layout.height || !self.layout.is_valid() {
+            return None;
+        }
+
+        let stride = self.layout.stride();
+        let row = self
+            .pixels
+            .get(y as usize * stride..(y as usize + 1) * stride)?;
+        let first = x as usize * self.layout.channels as usize;
+        let sample = |channel: usize| read_sample(row, first + channel, self.layout.bit_depth);
+
+        let pixel = match self.layout.color_type {
+            ColorType::Grayscale => Pixel::Grayscale(sample(0)),
+            ColorType::TrueColor => Pixel::TrueColor(sample(0), sample(1), sample(2)),
+            ColorType::IndexedColor => Pixel::IndexedColor(sample(0) as u8),
+            ColorType::GrayscaleAlpha => Pixel::GrayscaleAlpha(sample(0), sample(1)),
+            ColorType::TrueColorAlpha => {
+                Pixel::TrueColorAlpha(sample(0), sample(1), sample(2), sample(3))
+            }
+        };
+
+        return Some(pixel);
+    }
+
+    /// Checks whether the layout is valid and the pixels hold every row of it.
+    fn has_valid_pixels(&self) -> bool {
+        let size = self
+            .layout
+            .stride()
+            .checked_mul(self.layout.height as usize);
+        return self.layout.is_valid() && size.is_some_and(|size| self.pixels.len() >= size);
+    }
+
+    /// Returns an iterator over the packed pixel rows.
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        return self.pixels.chunks_exact(self.layout.stride());
+    }
+
+    /// Converts the pixels to 8-bit RGBA samples with the palette and transparency applied.
+    ///
+    /// As the fields are public, an empty vector is returned if the layout does not describe a valid image or the
+    /// pixels are too short for it.
+    pub fn as_rgba8(&self) -> Vec<u8> {
+        if !self.has_valid_pixels() {
+            return Vec::new();
+        }
+
+        return convert::to_rgba8(
+            &self.layout,
+            &self.pixels,
+            self.palette.as_deref().unwrap_or(&[]),
+            self.transparency.as_ref(),
+        );
+    }
+
+    /// Converts the pixels to 16-bit RGBA samples with the palette and transparency applied.
+    ///
+    /// As the fields are public, an empty vector is returned if the layout does not describe a valid image or the
+    /// pixels are too short for it.
+    pub fn as_rgba16(&self) -> Vec<u16> {
+        if !self.has_valid_pixels() {
+            return Vec::new();
+        }
+
+        return convert::to_rgba16(
+            &self.layout,
+            &self.pixels,
+            self.palette.as_deref().unwrap_or(&[]),
+            self.transparency.as_ref(),
+        );
+    }
+}
+
+/// Reads the sample at the provided index of a packed row.
+pub(crate) fn read_sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    return match bit_depth {
+        16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+        8 => row[index] as u16,
+        _ => {
+            let bit = index * bit_depth as usize;
+            let shift = 8 - bit_depth as usize - bit % 8;
+            ((row[bit / 8] >> shift) & ((1u8 << bit_depth) - 1)) as u16
+        }
+    };
+}
+
+impl PngInfo {
+    /// Decompresses, unfilters and de-interlaces the image data into an owned image.
+    ///
+    /// The samples of Apple optimized images are restored to the RGB(A) order with straight alpha.
+    pub fn decode(&self) -> Result<Image, ParserError> {
+        let layout = ImageLayout::from_header(&self.header);
+
+        let pixels = match self.cgbi {
+            Some(_) => {
+                let limit = filter::image_data_size(&self.header)
+                    .ok_or(ParserError::InvalidImageDataLength)?;
+                let data = inflate::decompress_raw_with_limit(&self.compressed_data.data, limit)?;
+                let mut pixels = filter::unfilter(&self.header, &data)?;
+                convert::restore_cgbi_samples(&layout, &mut pixels);
+                pixels
+            }
+            None => {
+                let data = inflate::decompress_image_data(&self.header, &self.compressed_data)?;
+                filter::unfilter(&self.header, &data)?
+            }
+        };
+
+        return Ok(Image {
+            layout,
+            palette: self.palette.as_ref().map(|palette| palette.entries.clone()),
+            transparency: self
+                .transparency
+                .as_ref()
+                .map(|transparency| transparency.transparency.clone()),
+            pixels,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::deflate::{self, CompressionLevel};
+    use crate::parser::Parser;
+    use crate::spec::{SIGNATURE, chunk_ids};
+    use crate::test_utils::chunk;
+    use crate::writer::Writer;
+
+    #[test]
+    fn test_decode_cgbi() {
+        // A 2x1 BGRA image with premultiplied alpha and raw deflate image data.
+        let scanline = [0, 30, 20, 10, 255, 0, 32, 64, 64];
+        let png = [
+            SIGNATURE.to_vec(),
+            chunk(b"CgBI", &[0x50, 0x00, 0x20, 0x06]),
+            chunk(b"IHDR", &[0, 0, 0, 2, 0, 0, 0, 1, 8, 6, 0, 0, 0]),
+            chunk(
+                b"IDAT",
+                &deflate::compress_raw(&scanline, CompressionLevel::Default),
+            ),
+            chunk(b"IEND", &[]),
+        ]
+        .concat();
+
+        let info = Parser::parse(&mut Cursor::new(&png)).unwrap();
+        assert!(info.cgbi.is_some());
+        assert_eq!(
+            info.decode().unwrap().as_rgba8(),
+            [10, 20, 30, 255, 255, 128, 0, 64]
+        );
+
+        let mut output = Vec::new();
+        Writer::write(&mut output, &info).unwrap();
+        assert_eq!(&output[12..16], &chunk_ids::CgBI.to_be_bytes());
+    }
+
+    #[test]
+    fn test_get_pixel_inconsistent_image() {
+        let layout = ImageLayout {
+            width: 4,
+            height: 4,
+            color_type: ColorType::TrueColor,
+            channels: 3,
+            bit_depth: 8,
+        };
+        let mut image = Image {
+            layout,
+            palette: None,
+            transparency: None,
+            pixels: vec![0; 12],
+        };
+        assert_eq!(image.get_pixel(3, 0), Some(Pixel::TrueColor(0, 0, 0)));
+        assert_eq!(image.get_pixel(0, 1), None);
+
+        image.pixels = vec![0; 48];
+        image.layout.bit_depth = 3;
+        assert_eq!(image.get_pixel(0, 0), None);
+    }
+
+    #[test]
+    fn test_as_rgba_inconsistent_image() {
+        let layout = ImageLayout {
+            width: 2,
+            height: 2,
+            color_type: ColorType::TrueColorAlpha,
+            channels: 4,
+            bit_depth: 8,
+        };
+        let mut image = Image {
+            layout,
+            palette: None,
+            transparency: None,
+            pixels: vec![0; 16],
+        };
+        assert_eq!(image.as_rgba8().len(), 16);
+        assert_eq!(image.as_rgba16().len(), 16);
+
+        // The channel count does not match the color type.
+        image.layout.channels = 1;
+        assert!(image.as_rgba8().is_empty());
+        assert!(image.as_rgba16().is_empty());
+
+        // The bit depth is not allowed for the color type.
+        image.layout.channels = 4;
+        image.layout.bit_depth = 12;
+        assert!(image.as_rgba8().is_empty());
+        assert!(image.as_rgba16().is_empty());
+
+        // The pixels hold only the first row.
+        image.layout.bit_depth = 8;
+        image.pixels.truncate(8);
+        assert!(image.as_rgba8().is_empty());
+        assert!(image.as_rgba16().is_empty());
+    }
+
+    #[test]
+    fn test_read_sample_bit_depths() {
+        let row = [0b1011_0100, 0x12, 0x34];
+        assert_eq!(read_sample(&row, 0, 1), 1);
+        assert_eq!(read_sample(&row, 1, 2), 0b11);
+        assert_eq!(read_sample(&row, 1, 4), 0b0100);
+        assert_eq!(read_sample(&row, 2, 8), 0x34);
+        assert_eq!(read_sample(&row[1..], 0, 16), 0x1234);
+    }
+}
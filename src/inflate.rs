@@ -0,0 +1,871 @@
+use crate::adler32;
+use crate::error::ParserError;
+use crate::filter;
+use crate::spec::{CompressedDataInfo, HeaderInfo};
+
+/// The largest distance a match can reach back into the decompressed data.
+const WINDOW_SIZE: usize = 32768;
+
+/// The maximum number of bits of a Huffman code.
+pub(crate) const MAX_CODE_LENGTH: usize = 15;
+
+/// The base lengths of the length symbols 257-285.
+pub(crate) const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+
+/// The number of extra bits of the length symbols 257-285.
+pub(crate) const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// The base distances of the distance symbols 0-29.
+pub(crate) const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+/// The number of extra bits of the distance symbols 0-29.
+pub(crate) const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// The order in which the code length code lengths are stored in a dynamic block header.
+pub(crate) const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompresses the concatenated `IDAT` data into the filtered scanline bytes.
+///
+/// The output is limited to the size of the scanlines described by the header, so that a small stream cannot
+/// expand into an arbitrarily large buffer.
+///
+/// # Arguments
+/// * `header` - The header describing the image layout.
+/// * `info` - The compressed data info produced by the parser.
+///
+/// # Returns
+/// * `Vec<u8>` - The filtered scanlines, each starting with its filter type byte.
+pub fn decompress_image_data(
+    header: &HeaderInfo,
+    info: &CompressedDataInfo,
+) -> Result<Vec<u8>, ParserError> {
+    let limit = filter::image_data_size(header).ok_or(ParserError::InvalidImageDataLength)?;
+    return decompress_with_limit(&info.data, limit);
+}
+
+/// Decompresses a zlib stream, verifying its header and Adler-32 checksum.
+///
+/// # Arguments
+/// * `data` - A byte slice containing a complete zlib stream.
+///
+/// # Returns
+/// * `Vec<u8>` - The decompressed data.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, ParserError> {
+    return decompress_with_limit(data, usize::MAX);
+}
+
+/// Decompresses a raw deflate stream without the zlib header and checksum, as used by Apple's optimized images.
+///
+/// # Arguments
+/// * `data` - A byte slice containing a complete deflate stream.
+///
+/// # Returns
+/// * `Vec<u8>` - The decompressed data.
+pub fn decompress_raw(data: &[u8]) -> Result<Vec<u8>, ParserError> {
+    return decompress_raw_with_limit(data, usize::MAX);
+}
+
+/// Decompresses a raw deflate stream, failing as soon as the output would exceed the provided limit.
+///
+/// # Arguments
+/// * `data` - A byte slice containing a complete deflate stream.
+/// * `limit` - The maximum number of decompressed bytes.
+///
+/// # Returns
+/// * `Vec<u8>` - The decompressed data.
+pub fn decompress_raw_with_limit(data: &[u8], limit: usize) -> Result<Vec<u8>, ParserError> {
+    let mut inflater = Inflater::new(data, limit);
+    inflater.inflate()?;
+
+    return Ok(inflater.output);
+}
+
+/// Decompresses a zlib stream, failing as soon as the output would exceed the provided limit.
+///
+/// # Arguments
+/// * `data` - A byte slice containing a complete zlib stream.
+/// * `limit` - The maximum number of decompressed bytes.
+///
+/// # Returns
+/// * `Vec<u8>` - The decompressed data.
+pub fn decompress_with_limit(data: &[u8], limit: usize) -> Result<Vec<u8>, ParserError> {
+    if data.len() < 2 {
+        return Err(ParserError::InvalidCompressedData);
+    }
+
+    check_zlib_header(data[0], data[1])?;
+
+    let mut inflater = Inflater::new(&data[2..], limit);
+    inflater.inflate()?;
+
+    let checksum = inflater.reader.read_aligned_u32()?;
+    if adler32::compute(&inflater.output) != checksum {
+        return Err(ParserError::CorruptedData);
+    }
+
+    return Ok(inflater.output);
+}
+
+/// Checks the two header bytes of a zlib stream.
+fn check_zlib_header(cmf: u8, flg: u8) -> Result<(), ParserError> {
+    // Only deflate with a window of at most 32768 bytes is allowed, and preset dictionaries are not used by PNG.
+    if cmf & 0x0f != 8 || cmf >> 4 > 7 || flg & 0x20 != 0 {
+        return Err(ParserError::InvalidCompressedData);
+    }
+
+    if !(((cmf as u16) << 8) | flg as u16).is_multiple_of(31) {
+        return Err(ParserError::InvalidCompressedData);
+    }
+
+    return Ok(());
+}
+
+/// Describes the part of a stream that a streaming inflater expects next.
+enum StreamStage {
+    ZlibHeader,
+    BlockHeader,
+    Stored(usize),
+    Compressed(Huffman, Huffman),
+    Checksum,
+    Done,
+}
+
+/// Represents the state of decompressing a zlib or raw deflate stream that arrives in pieces.
+///
+/// The input is decoded one symbol at a time, and a symbol that is cut off by the end of the available input is
+/// decoded again once more input arrives, so the output never holds bytes that may later turn out to be invalid.
+/// Output that has been consumed is dropped once it is beyond the reach of back-references.
+pub(crate) struct StreamInflater {
+    input: Vec<u8>,
+    consumed_bits: usize,
+    /// The most recent decompressed bytes, starting with those that may still be referenced by later matches.
+    output: Vec<u8>,
+    /// The position in `output` of the first byte that has not been consumed.
+    read: usize,
+    /// The number of decompressed bytes dropped from the front of `output`.
+    drained: usize,
+    /// The Adler-32 checksum of the decompressed bytes before `checksummed`.
+    checksum: u32,
+    checksummed: usize,
+    limit: usize,
+    stage: StreamStage,
+    is_final_block: bool,
+    has_checksum: bool,
+}
+
+impl StreamInflater {
+    /// Creates an inflater for a zlib stream, or a raw deflate stream as used by Apple's optimized images.
+    ///
+    /// # Arguments
+    /// * `is_raw` - Whether the stream is a raw deflate stream without the zlib header and checksum.
+    /// * `limit` - The maximum number of bytes the stream may decompress to.
+    pub(crate) fn new(is_raw: bool, limit: usize) -> Self {
+        return Self {
+            input: Vec::new(),
+            consumed_bits: 0,
+            output: Vec::new(),
+            read: 0,
+            drained: 0,
+            checksum: adler32::compute(&[]),
+            checksummed: 0,
+            limit,
+            stage: match is_raw {
+                true => StreamStage::BlockHeader,
+                false => StreamStage::ZlibHeader,
+            },
+            is_final_block: false,
+            has_checksum: !is_raw,
+        };
+    }
+
+    /// Returns the data decompressed so far that has not been consumed.
+    pub(crate) fn output(&self) -> &[u8] {
+        return &self.output[self.read..];
+    }
+
+    /// Marks the provided number of bytes at the start of `output` as consumed, so they can be dropped.
+    pub(crate) fn consume(&mut self, count: usize) {
+        self.read += count;
+    }
+
+    /// Returns whether the end of the stream has been decoded.
+    pub(crate) fn is_done(&self) -> bool {
+        return matches!(self.stage, StreamStage::Done);
+    }
+
+    /// Appends the provided compressed data and decompresses as much of the available input as possible.
+    ///
+    /// # Arguments
+    /// * `data` - The next piece of the compressed stream.
+    /// * `is_last` - Whether no more input follows, which turns running out of input into an error.
+    pub(crate) fn write(&mut self, data: &[u8], is_last: bool) -> Result<(), ParserError> {
+        if self.is_done() {
+            return match data.is_empty() {
+                true => Ok(()),
+                false => Err(ParserError::InvalidCompressedData),
+            };
+        }
+
+        self.input.extend_from_slice(data);
+
+        let start = self.consumed_bits / 8;
+        let input = std::mem::take(&mut self.input);
+        let mut inflater = Inflater::new(&input[start..], self.limit - self.drained);
+        inflater.output = std::mem::take(&mut self.output);
+        inflater.reader.read_bits((self.consumed_bits % 8) as u32)?;
+
+        let mut committed = (inflater.reader.consumed_bits(), inflater.output.len());
+        let result = loop {
+            match self.step(&mut inflater) {
+                Ok(true) => committed = (inflater.reader.consumed_bits(), inflater.output.len()),
+                Ok(false) => break Ok(()),
+                Err(_) if !is_last && inflater.reader.exhausted => {
+                    inflater.output.truncate(committed.1);
+                    break Ok(());
+                }
+                Err(e) => break Err(e),
+            }
+        };
+
+        self.output = inflater.output;
+        self.consumed_bits = start * 8 + committed.0;
+        self.input = input;
+        result?;
+
+        // The consumed input is never read again, so only the byte holding the next bits is kept.
+        let consumed_bytes = self.consumed_bits / 8;
+        self.input.drain(..consumed_bytes);
+        self.consumed_bits -= consumed_bytes * 8;
+
+        // Any byte past the end of the stream is data that does not belong to it.
+        if self.is_done() && self.input.len() > self.consumed_bits.div_ceil(8) {
+            return Err(ParserError::InvalidCompressedData);
+        }
+
+        self.checksum = adler32::update(self.checksum, &self.output[self.checksummed..]);
+        self.checksummed = self.output.len();
+
+        // Dropping the consumed output only once it exceeds a second window keeps the copying amortized.
+        let drainable = self.read.min(self.output.len().saturating_sub(WINDOW_SIZE));
+        if drainable >= WINDOW_SIZE {
+            self.output.drain(..drainable);
+            self.read -= drainable;
+            self.checksummed -= drainable;
+            self.drained += drainable;
+        }
+
+        return Ok(());
+    }
+
+    /// Decodes the next part of the stream, and returns whether the end of the stream has not been reached yet.
+    fn step(&mut self, inflater: &mut Inflater) -> Result<bool, ParserError> {
+        match &self.stage {
+            StreamStage::ZlibHeader => {
+                let cmf = inflater.reader.read_bits(8)? as u8;
+                let flg = inflater.reader.read_bits(8)? as u8;
+                check_zlib_header(cmf, flg)?;
+                self.stage = StreamStage::BlockHeader;
+            }
+            StreamStage::BlockHeader if self.is_final_block => {
+                self.stage = match self.has_checksum {
+                    true => StreamStage::Checksum,
+                    false => StreamStage::Done,
+                };
+            }
+            StreamStage::BlockHeader => {
+                let is_final = inflater.reader.read_bits(1)? == 1;
+                let stage = match inflater.reader.read_bits(2)? {
+                    0 => StreamStage::Stored(inflater.read_stored_length()?),
+                    1 => {
+                        let (literals, distances) = fixed_codes()?;
+                        StreamStage::Compressed(literals, distances)
+                    }
+                    2 => {
+                        let (literals, distances) = inflater.read_dynamic_codes()?;
+                        StreamStage::Compressed(literals, distances)
+                    }
+                    _ => return Err(ParserError::InvalidCompressedData),
+                };
+
+                self.is_final_block = is_final;
+                self.stage = stage;
+            }
+            StreamStage::Stored(0) => self.stage = StreamStage::BlockHeader,
+            StreamStage::Stored(remaining) => {
+                let bytes = inflater.reader.read_available_bytes(*remaining)?;
+                inflater.reserve(bytes.len())?;
+                inflater.output.extend_from_slice(bytes);
+                self.stage = StreamStage::Stored(remaining - bytes.len());
+            }
+            StreamStage::Compressed(literals, distances) => {
+                if inflater.inflate_symbol(literals, distances)? {
+                    self.stage = StreamStage::BlockHeader;
+                }
+            }
+            StreamStage::Checksum => {
+                let expected_checksum = inflater.reader.read_aligned_u32()?;
+                let checksum = adler32::update(self.checksum, &inflater.output[self.checksummed..]);
+                if checksum != expected_checksum {
+                    return Err(ParserError::CorruptedData);
+                }
+
+                self.stage = StreamStage::Done;
+            }
+            StreamStage::Done => return Ok(false),
+        }
+
+        return Ok(true);
+    }
+}
+
+/// Reads bits from a byte slice in the least-significant-bit-first order used by deflate.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit_buffer: u64,
+    bit_count: u32,
+    padding_bits: u32,
+    /// Whether a read has reached past the end of the data, which may be the cause of an error.
+    exhausted: bool,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        return Self {
+            data,
+            position: 0,
+            bit_buffer: 0,
+            bit_count: 0,
+            padding_bits: 0,
+            exhausted: false,
+        };
+    }
+
+    /// Returns the number of bits consumed from the start of the data.
+    fn consumed_bits(&self) -> usize {
+        return self.position * 8 + self.padding_bits as usize - self.bit_count as usize;
+    }
+
+    /// Fills the bit buffer, padding with zeros past the end of data so that lookups never stall.
+    fn refill(&mut self) {
+        while self.bit_count <= 56 {
+            let byte = match self.data.get(self.position) {
+                Some(&byte) => {
+                    self.position += 1;
+                    byte
+                }
+                None => {
+                    self.padding_bits += 8;
+                    self.exhausted = true;
+                    0
+                }
+            };
+
+            self.bit_buffer |= (byte as u64) << self.bit_count;
+            self.bit_count += 8;
+        }
+    }
+
+    fn peek(&mut self, count: u32) -> u32 {
+        if self.bit_count < count {
+            self.refill();
+        }
+
+        return (self.bit_buffer & ((1u64 << count) - 1)) as u32;
+    }
+
+    fn consume(&mut self, count: u32) -> Result<(), ParserError> {
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+
+        if self.padding_bits > self.bit_count {
+            return Err(ParserError::InvalidCompressedData);
+        }
+
+        return Ok(());
+    }
+
+    fn read_bits(&mut self, count: u32) -> Result<u32, ParserError> {
+        if count == 0 {
+            return Ok(0);
+        }
+
+        let value = self.peek(count);
+        self.consume(count)?;
+        return Ok(value);
+    }
+
+    /// Discards the remaining bits of the current byte and hands the buffered bytes back to the slice.
+    fn align_to_byte(&mut self) {
+        let skipped = self.bit_count % 8;
+        self.bit_buffer >>= skipped;
+        self.bit_count -= skipped;
+
+        let buffered = ((self.bit_count - self.padding_bits) / 8) as usize;
+        self.position -= buffered;
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+        self.padding_bits = 0;
+    }
+
+    fn read_aligned_bytes(&mut self, count: usize) -> Result<&'a [u8], ParserError> {
+        self.align_to_byte();
+
+        if self.data.len() - self.position < count {
+            self.exhausted = true;
+            return Err(ParserError::InvalidCompressedData);
+        }
+
+        let bytes = &self.data[self.position..self.position + count];
+        self.position += count;
+        return Ok(bytes);
+    }
+
+    /// Reads at most the provided number of bytes, failing only if no byte is available.
+    fn read_available_bytes(&mut self, count: usize) -> Result<&'a [u8], ParserError> {
+        self.align_to_byte();
+
+        let count = count.min(self.data.len() - self.position);
+        if count == 0 {
+            self.exhausted = true;
+            return Err(ParserError::InvalidCompressedData);
+        }
+
+        let bytes = &self.data[self.position..self.position + count];
+        self.position += count;
+        return Ok(bytes);
+    }
+
+    fn read_aligned_u32(&mut self) -> Result<u32, ParserError> {
+        let bytes = self.read_aligned_bytes(4)?;
+        return Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+    }
+}
+
+/// Represents a canonical Huffman code as a lookup table indexed by the next (bit-reversed) input bits.
+struct Huffman {
+    /// Each entry holds the symbol in the upper bits and the code length in the lower 4 bits.
+    table: Vec<u16>,
+    max_length: u32,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, ParserError> {
+        let mut counts = [0u16; MAX_CODE_LENGTH + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // Over-subscribed codes can never be decoded unambiguously, while incomplete ones are allowed.
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(ParserError::InvalidCompressedData);
+            }
+        }
+
+        let max_length = (1..=MAX_CODE_LENGTH)
+            .rev()
+            .find(|&length| counts[length] != 0)
+            .unwrap_or(1) as u32;
+
+        let mut next_code = [0u16; MAX_CODE_LENGTH + 1];
+        let mut code = 0u16;
+        for length in 1..=MAX_CODE_LENGTH {
+            code = (code + counts[length - 1]) << 1;
+            next_code[length] = code;
+        }
+
+        let mut table = vec![0u16; 1 << max_length];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length == 0 {
+                continue;
+            }
+
+            let code = next_code[length as usize];
+            next_code[length as usize] += 1;
+
+            let reversed = (code.reverse_bits() >> (16 - length as u32)) as usize;
+            let entry = ((symbol as u16) << 4) | length as u16;
+            for index in (reversed..table.len()).step_by(1 << length) {
+                table[index] = entry;
+            }
+        }
+
+        return Ok(Self { table, max_length });
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, ParserError> {
+        let entry = self.table[reader.peek(self.max_length) as usize];
+        let length = (entry & 0x0f) as u32;
+
+        if length == 0 {
+            return Err(ParserError::InvalidCompressedData);
+        }
+
+        reader.consume(length)?;
+        return Ok(entry >> 4);
+    }
+}
+
+/// Represents the state of decompressing a raw deflate stream.
+struct Inflater<'a> {
+    reader: BitReader<'a>,
+    output: Vec<u8>,
+    limit: usize,
+}
+
+impl<'a> Inflater<'a> {
+    fn new(data: &'a [u8], limit: usize) -> Self {
+        return Self {
+            reader: BitReader::new(data),
+            output: Vec::new(),
+            limit,
+        };
+    }
+
+    fn reserve(&self, count: usize) -> Result<(), ParserError> {
+        if self.limit - self.output.len() < count {
+            return Err(ParserError::DecompressedSizeLimitExceeded);
+        }
+
+        return Ok(());
+    }
+
+    fn inflate(&mut self) -> Result<(), ParserError> {
+        loop {
+            let is_final = self.reader.read_bits(1)? == 1;
+
+            match self.reader.read_bits(2)? {
+                0 => self.inflate_stored()?,
+                1 => self.inflate_fixed()?,
+                2 => self.inflate_dynamic()?,
+                _ => return Err(ParserError::InvalidCompressedData),
+            }
+
+            if is_final {
+                break;
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn inflate_stored(&mut self) -> Result<(), ParserError> {
+        let length = self.read_stored_length()?;
+
+        let bytes = self.reader.read_aligned_bytes(length)?;
+        self.reserve(bytes.len())?;
+        self.output.extend_from_slice(bytes);
+
+        return Ok(());
+    }
+
+    fn inflate_fixed(&mut self) -> Result<(), ParserError> {
+        let (literals, distances) = fixed_codes()?;
+        return self.inflate_block(&literals, &distances);
+    }
+
+    fn inflate_dynamic(&mut self) -> Result<(), ParserError> {
+        let (literals, distances) = self.read_dynamic_codes()?;
+        return self.inflate_block(&literals, &distances);
+    }
+
+    /// Reads the length of a stored block and checks it against its complement.
+    fn read_stored_length(&mut self) -> Result<usize, ParserError> {
+        let header = self.reader.read_aligned_bytes(4)?;
+        let length = u16::from_le_bytes([header[0], header[1]]);
+        let complement = u16::from_le_bytes([header[2], header[3]]);
+
+        if length != !complement {
+            return Err(ParserError::InvalidCompressedData);
+        }
+
+        return Ok(length as usize);
+    }
+
+    /// Reads the literal/length and distance codes of a dynamic block header.
+    fn read_dynamic_codes(&mut self) -> Result<(Huffman, Huffman), ParserError> {
+        let literal_count = self.reader.read_bits(5)? as usize + 257;
+        let distance_count = self.reader.read_bits(5)? as usize + 1;
+        let code_length_count = self.reader.read_bits(4)? as usize + 4;
+
+        if literal_count > 286 || distance_count > 30 {
+            return Err(ParserError::InvalidCompressedData);
+        }
+
+        let mut code_lengths = [0u8; 19];
+        for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_lengths[index] = self.reader.read_bits(3)? as u8;
+        }
+
+        let code_length_code = Huffman::new(&code_lengths)?;
+
+        let mut lengths = vec![0u8; literal_count + distance_count];
+        let mut index = 0;
+        while index < lengths.len() {
+            let symbol = code_length_code.decode(&mut self.reader)?;
+
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    if index == 0 {
+                        return Err(ParserError::InvalidCompressedData);
+                    }
+                    (lengths[index - 1], 3 + self.reader.read_bits(2)? as usize)
+                }
+                17 => (0, 3 + self.reader.read_bits(3)? as usize),
+                18 => (0, 11 + self.reader.read_bits(7)? as usize),
+                _ => return Err(ParserError::InvalidCompressedData),
+            };
+
+            if index + repeat > lengths.len() {
+                return Err(ParserError::InvalidCompressedData);
+            }
+
+            lengths[index..index + repeat].fill(value);
+            index += repeat;
+        }
+
+        // The end-of-block symbol must be encodable, otherwise the block could never terminate.
+        if lengths[256] == 0 {
+            return Err(ParserError::InvalidCompressedData);
+        }
+
+        let literals = Huffman::new(&lengths[..literal_count])?;
+        let distances = Huffman::new(&lengths[literal_count..])?;
+
+        return Ok((literals, distances));
+    }
+
+    fn inflate_block(
+        &mut self,
+        literals: &Huffman,
+        distances: &Huffman,
+    ) -> Result<(), ParserError> {
+        while !self.inflate_symbol(literals, distances)? {}
+
+        return Ok(());
+    }
+
+    /// Decodes a single literal or match, and returns whether the end of the block has been reached.
+    fn inflate_symbol(
+        &mut self,
+        literals: &Huffman,
+        distances: &Huffman,
+    ) -> Result<bool, ParserError> {
+        let symbol = literals.decode(&mut self.reader)? as usize;
+
+        if symbol < 256 {
+            self.reserve(1)?;
+            self.output.push(symbol as u8);
+            return Ok(false);
+        }
+
+        if symbol == 256 {
+            return Ok(true);
+        }
+
+        let symbol = symbol - 257;
+        if symbol >= LENGTH_BASE.len() {
+            return Err(ParserError::InvalidCompressedData);
+        }
+
+        let length = LENGTH_BASE[symbol] as usize
+            + self.reader.read_bits(LENGTH_EXTRA[symbol] as u32)? as usize;
+
+        let symbol = distances.decode(&mut self.reader)? as usize;
+        if symbol >= DISTANCE_BASE.len() {
+            return Err(ParserError::InvalidCompressedData);
+        }
+
+        let distance = DISTANCE_BASE[symbol] as usize
+            + self.reader.read_bits(DISTANCE_EXTRA[symbol] as u32)? as usize;
+
+        if distance > self.output.len() {
+            return Err(ParserError::InvalidCompressedData);
+        }
+
+        self.reserve(length)?;
+
+        let start = self.output.len() - distance;
+        if distance >= length {
+            self.output.extend_from_within(start..start + length);
+        } else {
+            // Overlapping copies repeat the most recent bytes, so they must be copied one at a time.
+            for offset in 0..length {
+                let byte = self.output[start + offset];
+                self.output.push(byte);
+            }
+        }
+
+        return Ok(false);
+    }
+}
+
+/// Builds the literal/length and distance codes of a fixed block.
+fn fixed_codes() -> Result<(Huffman, Huffman), ParserError> {
+    let mut lengths = [0u8; 288 + 30];
+    lengths[0..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..288].fill(8);
+    lengths[288..].fill(5);
+
+    let literals = Huffman::new(&lengths[..288])?;
+    let distances = Huffman::new(&lengths[288..])?;
+
+    return Ok((literals, distances));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deflate::{self, CompressionLevel};
+    use crate::spec::{ColorType, CompressionMethod, FilterMethod, InterlaceMethod};
+
+    #[test]
+    fn test_decompress_stored_block() {
+        let data = [120, 1, 1, 3, 0, 252, 255, 97, 98, 99, 2, 77, 1, 39];
+        assert_eq!(decompress(&data).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn test_decompress_fixed_block() {
+        let data = [
+            120, 218, 203, 72, 205, 201, 201, 87, 200, 64, 39, 1, 104, 3, 8, 177,
+        ];
+        assert_eq!(decompress(&data).unwrap(), b"hello hello hello hello");
+    }
+
+    #[test]
+    fn test_decompress_dynamic_block() {
+        let data = [
+            120, 218, 85, 193, 193, 13, 128, 32, 12, 0, 192, 85, 58, 128, 113, 0, 183, 17, 138,
+            173, 9, 193, 166, 64, 141, 219, 11, 232, 67, 239, 0, 254, 166, 102, 94, 214, 193, 57,
+            239, 17, 195, 215, 214, 16, 17, 51, 239, 175, 24, 83, 119, 200, 160, 170, 185, 43, 143,
+            90, 205, 236, 188, 10, 7, 144, 68, 144, 37, 120, 192, 27, 48, 216, 41, 30,
+        ];
+        let expected = b"                ,,,,.:aaaaaabbccddeeeeeeeeeeeeeffffggghhhiiiiiiiillnnnnnopppppprrrssssstttttttuuvvvwythe png spec d";
+        assert_eq!(decompress(&data).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_decompress_size_limit() {
+        let data = [
+            120, 218, 203, 72, 205, 201, 201, 87, 200, 64, 39, 1, 104, 3, 8, 177,
+        ];
+        assert!(decompress_with_limit(&data, 23).is_ok());
+        assert!(matches!(
+            decompress_with_limit(&data, 22),
+            Err(ParserError::DecompressedSizeLimitExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_decompress_checksum_mismatch() {
+        let data = [120, 1, 1, 3, 0, 252, 255, 97, 98, 99, 2, 77, 1, 40];
+        assert!(matches!(decompress(&data), Err(ParserError::CorruptedData)));
+    }
+
+    #[test]
+    fn test_decompress_image_data_limit() {
+        let header = HeaderInfo {
+            width: 3,
+            height: 2,
+            bit_depth: 8,
+            color_type: ColorType::Grayscale,
+            compression_method: CompressionMethod::Deflate,
+            filter_method: FilterMethod::Adaptive,
+            interlace_method: InterlaceMethod::None,
+        };
+        let info = |size: usize| CompressedDataInfo {
+            chunk_count: 1,
+            chunk_lengths: Vec::new(),
+            data: deflate::compress(&vec![0; size], CompressionLevel::Default),
+        };
+
+        assert_eq!(decompress_image_data(&header, &info(8)).unwrap().len(), 8);
+        assert!(matches!(
+            decompress_image_data(&header, &info(4096)),
+            Err(ParserError::DecompressedSizeLimitExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_stream_inflater() {
+        // The data is larger than two windows, so consumed output is dropped while the stream is decoded.
+        let data: Vec<u8> = (0..100_000u64)
+            .map(|i| ((i * i % 65521 * 40503) >> 8) as u8)
+            .collect();
+        for level in [
+            CompressionLevel::Stored,
+            CompressionLevel::Fastest,
+            CompressionLevel::Maximum,
+        ] {
+            let compressed = deflate::compress(&data, level);
+
+            let mut inflater = StreamInflater::new(false, data.len());
+            let mut output = Vec::new();
+            for piece in compressed.chunks(997) {
+                inflater.write(piece, false).unwrap();
+                output.extend_from_slice(inflater.output());
+                inflater.consume(inflater.output().len());
+                assert!(data.starts_with(&output));
+            }
+
+            inflater.write(&[], true).unwrap();
+            assert!(inflater.is_done());
+            assert!(inflater.output.len() < data.len());
+            assert_eq!(output, data);
+        }
+
+        let compressed = deflate::compress(&data, CompressionLevel::Default);
+        let mut inflater = StreamInflater::new(false, usize::MAX);
+        assert!(inflater.write(&compressed[..100], true).is_err());
+
+        let mut inflater = StreamInflater::new(false, data.len() - 1);
+        assert!(matches!(
+            inflater.write(&compressed, true),
+            Err(ParserError::DecompressedSizeLimitExceeded)
+        ));
+
+        // Data following the end of the stream is rejected, whether it arrives with the stream or after it.
+        let mut inflater = StreamInflater::new(false, usize::MAX);
+        assert!(matches!(
+            inflater.write(&[&compressed[..], &[0]].concat(), false),
+            Err(ParserError::InvalidCompressedData)
+        ));
+
+        let mut inflater = StreamInflater::new(false, usize::MAX);
+        inflater.write(&compressed, false).unwrap();
+        assert!(matches!(
+            inflater.write(&[0], true),
+            Err(ParserError::InvalidCompressedData)
+        ));
+    }
+
+    #[test]
+    fn test_decompress_truncated_stream() {
+        let data = [120, 218, 203, 72, 205, 201];
+        assert!(matches!(
+            decompress(&data),
+            Err(ParserError::InvalidCompressedData)
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::error::ParserError;
+use crate::filter;
+use crate::spec::HeaderInfo;
+
+/// Describes a reduced image of Adam7 interlacing by its starting column, starting row, column step and row step.
+pub(crate) const ADAM7_PASSES: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Returns the width and height of the reduced image of the provided Adam7 pass.
+pub(crate) fn pass_size(pass: usize, width: u32, height: u32) -> (u32, u32) {
+    let (x_start, y_start, x_step, y_step) = ADAM7_PASSES[pass];
+
+    let pass_width = match width > x_start {
+        true => (width - x_start).div_ceil(x_step),
+        false => 0,
+    };
+    let pass_height = match height > y_start {
+        true => (height - y_start).div_ceil(y_step),
+        false => 0,
+    };
+
+    return (pass_width, pass_height);
+}
+
+/// Reconstructs the scanlines of an Adam7 interlaced image from its decompressed data.
+///
+/// # Arguments
+/// * `header` - The header describing the image layout.
+/// * `data` - The decompressed data containing the seven filtered reduced images one after another.
+///
+/// # Returns
+/// * `Vec<u8>` - The unfiltered full-resolution scanlines, in the same layout as a non-interlaced image.
+pub fn deinterlace(header: &HeaderInfo, data: &[u8]) -> Result<Vec<u8>, ParserError> {
+    let bits_per_pixel = header.bits_per_pixel();
+    let stride = header.stride();
+
+    // The output is no larger than the input, so checking the input first also bounds the allocation.
+    match filter::image_data_size(header) {
+        Some(size) if data.len() >= size => {}
+        _ => return Err(ParserError::InvalidImageDataLength),
+    }
+
+    let mut output = vec![0u8; stride * header.height as usize];
+    let mut offset = 0;
+
+    for (pass, &(x_start, y_start, x_step, y_step)) in ADAM7_PASSES.iter().enumerate() {
+        let (pass_width, pass_height) = pass_size(pass, header.width, header.height);
+
+        // Empty passes are not transmitted at all, not even their filter type bytes.
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+
+        let pass_stride = filter::stride(pass_width, bits_per_pixel);
+        let length = (pass_stride + 1) * pass_height as usize;
+
+        if data.len() < offset + length {
+            return Err(ParserError::InvalidImageDataLength);
+        }
+
+        let pixels = filter::unfilter_scanlines(
+            &data[offset..offset + length],
+            pass_width,
+            pass_height,
+            bits_per_pixel,
+        )?;
+        offset += length;
+
+        for (pass_y, row) in pixels.chunks_exact(pass_stride).enumerate() {
+            let y = (y_start + pass_y as u32 * y_step) as usize;
+            let target = &mut output[y * stride..(y + 1) * stride];
+
+            for pass_x in 0..pass_width as usize {
+                let x = (x_start + pass_x as u32 * x_step) as usize;
+                copy_pixel(row, pass_x, target, x, bits_per_pixel);
+            }
+        }
+    }
+
+    return Ok(output);
+}
+
+/// Extracts the reduced image of the provided Adam7 pass from full-resolution unfiltered scanlines.
+pub(crate) fn extract_pass(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    bits_per_pixel: usize,
+    pass: usize,
+) -> Vec<u8> {
+    let (x_start, y_start, x_step, y_step) = ADAM7_PASSES[pass];
+    let (pass_width, pass_height) = pass_size(pass, width, height);
+    if pass_width == 0 || pass_height == 0 {
+        return Vec::new();
+    }
+
+    let stride = filter::stride(width, bits_per_pixel);
+    let pass_stride = filter::stride(pass_width, bits_per_pixel);
+    let mut output = vec![0u8; pass_stride * pass_height as usize];
+
+    for (pass_y, target) in output.chunks_exact_mut(pass_stride).enumerate() {
+        let y = (y_start + pass_y as u32 * y_step) as usize;
+        let row = &pixels[y * stride..(y + 1) * stride];
+
+        for pass_x in 0..pass_width as usize {
+            let x = (x_start + pass_x as u32 * x_step) as usize;
+            copy_pixel(row, x, target, pass_x, bits_per_pixel);
+        }
+    }
+
+    return output;
+}
+
+/// Copies a pixel between two packed scanlines, handling pixels smaller than a byte.
+pub(crate) fn copy_pixel(
+    source: &[u8],
+    source_x: usize,
+    target: &mut [u8],
+    target_x: usize,
+    bits_per_pixel: usize,
+) {
+    if bits_per_pixel >= 8 {
+        let size = bits_per_pixel / 8;
+        target[target_x * size..(target_x + 1) * size]
+            .copy_from_slice(&source[source_x * size..(source_x + 1) * size]);
+        return;
+    }
+
+    let mask = (1u8 << bits_per_pixel) - 1;
+
+    let source_bit = source_x * bits_per_pixel;
+    let source_shift = 8 - bits_per_pixel - source_bit % 8;
+    let value = (source[source_bit / 8] >> source_shift) & mask;
+
+    let target_bit = target_x * bits_per_pixel;
+    let target_shift = 8 - bits_per_pixel - target_bit % 8;
+    let byte = &mut target[target_bit / 8];
+    *byte = (*byte & !(mask << target_shift)) | (value << target_shift);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::{ColorType, CompressionMethod, FilterMethod, InterlaceMethod};
+
+    #[test]
+    fn test_pass_size_small_image() {
+        let sizes: Vec<(u32, u32)> = (0..7).map(|pass| pass_size(pass, 3, 3)).collect();
+        assert_eq!(
+            sizes,
+            [(1, 1), (0, 1), (1, 0), (1, 1), (2, 1), (1, 2), (3, 1)]
+        );
+    }
+
+    #[test]
+    fn test_copy_sub_byte_pixel() {
+        let source = [0b0110_0000];
+        let mut target = [0b1111_1111];
+        copy_pixel(&source, 1, &mut target, 2, 2);
+        assert_eq!(target, [0b1111_1011]);
+    }
+
+    #[test]
+    fn test_deinterlace_known_output() {
+        let header = HeaderInfo {
+            width: 8,
+            height: 8,
+            bit_depth: 8,
+            color_type: ColorType::Grayscale,
+            compression_method: CompressionMethod::Deflate,
+            filter_method: FilterMethod::Adaptive,
+            interlace_method: InterlaceMethod::Adam7,
+        };
+
+        // The samples of pass N count up from 0xN0 in scanline order, and every scanline has no filter.
+        let data: Vec<u8> = [
+            &[0, 0x10][..],
+            &[0, 0x20],
+            &[0, 0x30, 0x31],
+            &[0, 0x40, 0x41],
+            &[0, 0x42, 0x43],
+            &[0, 0x50, 0x51, 0x52, 0x53],
+            &[0, 0x54, 0x55, 0x56, 0x57],
+            &[0, 0x60, 0x61, 0x62, 0x63],
+            &[0, 0x64, 0x65, 0x66, 0x67],
+            &[0, 0x68, 0x69, 0x6a, 0x6b],
+            &[0, 0x6c, 0x6d, 0x6e, 0x6f],
+            &[0, 0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0x76, 0x77],
+            &[0, 0x78, 0x79, 0x7a, 0x7b, 0x7c, 0x7d, 0x7e, 0x7f],
+            &[0, 0x80, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87],
+            &[0, 0x88, 0x89, 0x8a, 0x8b, 0x8c, 0x8d, 0x8e, 0x8f],
+        ]
+        .concat();
+
+        // Each sample lands on the pixel that the Adam7 pattern assigns to its pass.
+        let expected = [
+            [0x10, 0x60, 0x40, 0x61, 0x20, 0x62, 0x41, 0x63],
+            [0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0x76, 0x77],
+            [0x50, 0x64, 0x51, 0x65, 0x52, 0x66, 0x53, 0x67],
+            [0x78, 0x79, 0x7a, 0x7b, 0x7c, 0x7d, 0x7e, 0x7f],
+            [0x30, 0x68, 0x42, 0x69, 0x31, 0x6a, 0x43, 0x6b],
+            [0x80, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87],
+            [0x54, 0x6c, 0x55, 0x6d, 0x56, 0x6e, 0x57, 0x6f],
+            [0x88, 0x89, 0x8a, 0x8b, 0x8c, 0x8d, 0x8e, 0x8f],
+        ];
+
+        assert_eq!(
+            deinterlace(&header, &data).unwrap(),
+            expected.as_flattened()
+        );
+    }
+
+    #[test]
+    fn test_deinterlace_oversized_header() {
+        let header = HeaderInfo {
+            width: i32::MAX as u32,
+            height: i32::MAX as u32,
+            bit_depth: 16,
+            color_type: ColorType::TrueColorAlpha,
+            compression_method: CompressionMethod::Deflate,
+            filter_method: FilterMethod::Adaptive,
+            interlace_method: InterlaceMethod::Adam7,
+        };
+        assert!(matches!(
+            deinterlace(&header, &[0; 64]),
+            Err(ParserError::InvalidImageDataLength)
+        ));
+    }
+}
@@ -1,5 +1,29 @@
+#![allow(clippy::needless_return)]
+
+pub mod adler32;
+pub mod animation;
+#[cfg(feature = "async")]
+pub mod async_parser;
+pub mod chunk;
+pub mod color;
+pub mod convert;
 pub mod crc32;
+pub mod deflate;
+pub mod encoder;
 pub mod error;
+pub mod exif;
+pub mod filter;
+pub mod handler;
+pub mod image;
+pub mod inflate;
+pub mod interlace;
 pub mod parser;
+pub mod probe;
+pub mod slice;
 pub mod spec;
+pub mod stream;
+#[cfg(test)]
+mod test_utils;
+pub mod text;
 pub mod utils;
+pub mod writer;
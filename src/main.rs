@@ -1,24 +1,32 @@
+#![allow(clippy::needless_return)]
+
 use png_rs::parser::Parser;
-use std::{env, fs, process::ExitCode};
+use std::{env, fs, io, process::ExitCode};
 
 fn main() -> ExitCode {
     let args: Vec<String> = env::args().collect();
     if args.len() == 1 {
         let exe = &args[0];
-        println!("Usage: {exe} <file>");
+        println!("Usage: {exe} <file | ->");
         return ExitCode::FAILURE;
     }
 
     let filepath = &args[1];
-    let mut file = match fs::File::open(filepath) {
-        Ok(f) => f,
-        Err(ref e) => {
-            println!("Unable to open {}: {}", filepath, e);
-            return ExitCode::FAILURE;
-        }
+    let result = if filepath == "-" {
+        Parser::parse(&mut io::stdin().lock())
+    } else {
+        let mut file = match fs::File::open(filepath) {
+            Ok(f) => f,
+            Err(ref e) => {
+                println!("Unable to open {}: {}", filepath, e);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        Parser::parse(&mut file)
     };
 
-    return match Parser::parse(&mut file) {
+    return match result {
         Ok(info) => {
             println!("{:#?}", info);
             ExitCode::SUCCESS
@@ -1,16 +1,172 @@
-use std::io::{Read, Seek};
+#![allow(
+    clippy::nonminimal_bool,
+    clippy::manual_is_multiple_of,
+    clippy::needless_borrow
+)]
 
-use crate::crc32;
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::chunk::ChunkReader;
 use crate::error::ParserError;
+use crate::exif;
+use crate::handler::{ChunkContext, ChunkHandler, DynChunkHandler};
+use crate::slice::{
+    BorrowedCompressedTextualData, BorrowedInternationalTextualData, BorrowedTextualData,
+};
 use crate::spec::*;
 use crate::utils;
 
+/// Describes how the parser handles the data of `IDAT` chunks read from a source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageDataHandling {
+    /// The data is kept in `CompressedDataInfo::data`.
+    #[default]
+    Buffer,
+    /// The data is read to check its CRC, and only its position is kept in `PngInfo::image_data_spans`.
+    Verify,
+    /// The data is skipped without checking its CRC, and only its position is kept in `PngInfo::image_data_spans`.
+    Skip,
+}
+
+/// Represents the options that customize how PNG data is parsed.
+#[derive(Default)]
+pub struct ParserOptions {
+    handlers: HashMap<ChunkId, Box<dyn DynChunkHandler>>,
+    image_data: ImageDataHandling,
+}
+
+impl ParserOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Registers a handler for the chunks of the provided type.
+    ///
+    /// Handlers are only consulted for chunks that the parser does not recognize, and a handler registered
+    /// again for the same chunk type replaces the previous one.
+    ///
+    /// # Arguments
+    /// * `chunk_type` - The chunk type to handle.
+    /// * `handler` - The handler that turns the chunk data into a typed value.
+    ///
+    /// # Returns
+    /// * `Self` - The options with the handler registered.
+    pub fn with_handler<Handler: ChunkHandler + 'static>(
+        mut self,
+        chunk_type: [u8; 4],
+        handler: Handler,
+    ) -> Self {
+        self.handlers
+            .insert(u32::from_be_bytes(chunk_type), Box::new(handler));
+        return self;
+    }
+
+    /// Sets how the data of `IDAT` chunks is handled when parsing from a source.
+    ///
+    /// Parsing from a buffer always borrows the data, so this option only affects `Parser`.
+    ///
+    /// # Arguments
+    /// * `handling` - Whether the data is kept, only verified, or skipped.
+    ///
+    /// # Returns
+    /// * `Self` - The options with the handling set.
+    pub fn with_image_data_handling(mut self, handling: ImageDataHandling) -> Self {
+        self.image_data = handling;
+        return self;
+    }
+}
+
 /// Represents a parser that handles PNG data.
-pub struct Parser<'a, Source: Read + Seek> {
-    source: &'a mut Source,
+///
+/// Chunks are read strictly in order, so the source does not need to be seekable or buffered.
+pub struct Parser<'a, Source: Read> {
+    chunks: ChunkReader<'a, Source>,
+    state: ParserState<'a>,
+}
+
+impl<'a, Source: Read> Parser<'a, Source> {
+    /// Tries to parse PNG data from provided source.
+    pub fn parse(source: &'a mut Source) -> Result<PngInfo, ParserError> {
+        return Parser::parse_with_options(source, &ParserOptions::default());
+    }
+
+    /// Tries to parse PNG data from provided source with the provided options.
+    ///
+    /// # Arguments
+    /// * `source` - The source to read the PNG data from.
+    /// * `options` - The options that customize the parsing.
+    ///
+    /// # Returns
+    /// * `Result<PngInfo, ParserError>` - The parsed info, or the error that stopped the parsing.
+    pub fn parse_with_options(
+        source: &'a mut Source,
+        options: &'a ParserOptions,
+    ) -> Result<PngInfo, ParserError> {
+        let mut parser = Self {
+            chunks: ChunkReader::new(source)?,
+            state: ParserState::new(Some(options)),
+        };
+
+        parser.parse_chunks()?;
+
+        return parser.state.collect();
+    }
+
+    fn parse_chunks(&mut self) -> Result<(), ParserError> {
+        while !self.state.is_finished() {
+            let image_data = self.state.image_data_handling();
+            let Some(chunk) = self.chunks.next_with(image_data) else {
+                break;
+            };
+
+            let chunk = chunk?;
+            if !chunk.crc_ok {
+                return Err(ParserError::CorruptedData);
+            }
+
+            if chunk.chunk_type == chunk_ids::IDAT && image_data != ImageDataHandling::Buffer {
+                self.state.process_image_data_span(DataSpan {
+                    offset: chunk.offset + 8,
+                    length: chunk.length,
+                })?;
+            } else {
+                self.state.process_chunk(chunk.chunk_type, &chunk.data)?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+/// Describes how a processed chunk was kept by the parser state.
+#[derive(Debug, Clone)]
+pub(crate) enum ChunkDisposition<'d> {
+    /// The chunk was recognized, or turned into a custom chunk by a registered handler.
+    Parsed,
+    /// The chunk was kept as an unknown chunk.
+    Unknown,
+    /// The chunk was a `tEXt` chunk, whose parsed value borrows from the chunk data.
+    Text(BorrowedTextualData<'d>),
+    /// The chunk was a `zTXt` chunk, whose parsed value borrows from the chunk data.
+    CompressedText(BorrowedCompressedTextualData<'d>),
+    /// The chunk was an `iTXt` chunk, whose parsed value borrows from the chunk data.
+    InternationalText(BorrowedInternationalTextualData<'d>),
+}
+
+/// Represents the chunk validation state and the info parsed so far, independently of where the chunks come from.
+pub(crate) struct ParserState<'a> {
+    /// The options that customize the parsing, or `None` for the default options.
+    options: Option<&'a ParserOptions>,
+    retain_payloads: bool,
+    previous_chunk: Option<ChunkId>,
+    is_unknown: bool,
+    cgbi: Option<CgBIInfo>,
     header: Option<HeaderInfo>,
     palette: Option<PaletteInfo>,
     compressed_data: Option<CompressedDataInfo>,
+    image_data_spans: Vec<DataSpan>,
     trailer: Option<TrailerInfo>,
     transparency: Option<TransparencyInfo>,
     gamma: Option<GammaInfo>,
@@ -26,21 +182,43 @@ pub struct Parser<'a, Source: Read + Seek> {
     suggested_palettes: Vec<SuggestedPaletteInfo>,
     palette_histogram: Option<PaletteHistogramInfo>,
     last_modification: Option<LastModificationInfo>,
+    image_offset: Option<ImageOffsetInfo>,
+    pixel_calibration: Option<PixelCalibrationInfo>,
+    physical_scale: Option<PhysicalScaleInfo>,
+    stereo_image: Option<StereoImageInfo>,
+    gif_graphic_controls: Vec<GifGraphicControlInfo>,
+    gif_application_extensions: Vec<GifApplicationExtensionInfo>,
+    gif_plain_text_extensions: Vec<GifPlainTextExtensionInfo>,
+    coding_independent_code_points: Option<CodingIndependentCodePointsInfo>,
+    mastering_display_color_volume: Option<MasteringDisplayColorVolumeInfo>,
+    content_light_level: Option<ContentLightLevelInfo>,
+    exif: Option<ExifInfo>,
     unknown_chunks: Vec<UnknownChunkInfo>,
+    unknown_chunk_count: usize,
+    chunk_order: Vec<[u8; 4]>,
+    custom_chunks: Vec<CustomChunkInfo>,
+    animation_control: Option<AnimationControlInfo>,
+    animation_frames: Vec<FrameInfo>,
+    sequence_number: u32,
 }
 
-impl<'a, Source: Read + Seek> Parser<'a, Source> {
+impl<'a> ParserState<'a> {
     const BEFORE_PLTE_CHUNK: u8 = 1;
     const AFTER_PLTE_CHUNK: u8 = 2;
     const BEFORE_IDAT_CHUNK: u8 = 4;
 
-    /// Tries to parse PNG data from provided source.
-    pub fn parse(source: &'a mut Source) -> Result<PngInfo, ParserError> {
-        let mut parser = Self {
-            source,
+    /// Creates a state that keeps the data of every chunk, using the default options if none are provided.
+    pub(crate) fn new(options: Option<&'a ParserOptions>) -> Self {
+        return Self {
+            options,
+            retain_payloads: true,
+            previous_chunk: None,
+            is_unknown: false,
+            cgbi: None,
             header: None,
             palette: None,
             compressed_data: None,
+            image_data_spans: Vec::new(),
             trailer: None,
             transparency: None,
             gamma: None,
@@ -56,107 +234,61 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
             suggested_palettes: Vec::new(),
             palette_histogram: None,
             last_modification: None,
+            image_offset: None,
+            pixel_calibration: None,
+            physical_scale: None,
+            stereo_image: None,
+            gif_graphic_controls: Vec::new(),
+            gif_application_extensions: Vec::new(),
+            gif_plain_text_extensions: Vec::new(),
+            coding_independent_code_points: None,
+            mastering_display_color_volume: None,
+            content_light_level: None,
+            exif: None,
             unknown_chunks: Vec::new(),
+            unknown_chunk_count: 0,
+            chunk_order: Vec::new(),
+            custom_chunks: Vec::new(),
+            animation_control: None,
+            animation_frames: Vec::new(),
+            sequence_number: 0,
         };
-
-        parser.validate_signature()?;
-        parser.parse_header()?;
-        parser.parse_chunks()?;
-
-        return parser.collect();
-    }
-
-    fn validate_signature(&mut self) -> Result<(), ParserError> {
-        let mut signature = [0u8; 8];
-        utils::read_to(self.source, &mut signature)?;
-        if signature != SIGNATURE {
-            return Err(ParserError::InvalidSignature);
-        }
-
-        return Ok(());
     }
 
-    fn parse_header(&mut self) -> Result<(), ParserError> {
-        let length = utils::read_u32(self.source)?;
-        let type_and_data = utils::read_bytes(self.source, 4 + length as usize)?;
-        let crc = utils::read_u32(self.source)?;
-
-        if crc32::compute(&type_and_data) != crc {
-            return Err(ParserError::CorruptedData);
-        }
-
-        let chunk_type = utils::to_u32(&type_and_data[..4]);
-        let data = &type_and_data[4..];
-
-        if chunk_type != chunk_ids::IHDR {
-            return Err(ParserError::MissingRequiredChunk(chunk_ids::IHDR));
-        }
-
-        if length != 13 {
-            return Err(ParserError::InvalidChunkLength(chunk_ids::IHDR));
-        }
-
-        let header_info = HeaderInfo {
-            width: utils::to_u32(&data[0..4]),
-            height: utils::to_u32(&data[4..8]),
-            bit_depth: data[8],
-            color_type: match data[9] {
-                0 => ColorType::Grayscale,
-                2 => ColorType::TrueColor,
-                3 => ColorType::IndexedColor,
-                4 => ColorType::GrayscaleAlpha,
-                6 => ColorType::TrueColorAlpha,
-                _ => return Err(ParserError::InvalidFieldValue),
-            },
-            compression_method: match data[10] {
-                0 => CompressionMethod::Deflate,
-                _ => return Err(ParserError::InvalidFieldValue),
-            },
-            filter_method: match data[11] {
-                0 => FilterMethod::Adaptive,
-                _ => return Err(ParserError::InvalidFieldValue),
-            },
-            interlace_method: match data[12] {
-                0 => InterlaceMethod::None,
-                1 => InterlaceMethod::Adam7,
-                _ => return Err(ParserError::InvalidFieldValue),
-            },
+    /// Creates a state that validates the image data, textual and unknown chunks without keeping their data,
+    /// which the caller borrows from its own buffer instead.
+    pub(crate) fn without_payloads(options: &'a ParserOptions) -> Self {
+        return Self {
+            retain_payloads: false,
+            ..Self::new(Some(options))
         };
-
-        if header_info.width == 0 || header_info.height == 0 {
-            return Err(ParserError::InvalidFieldValue);
-        }
-
-        let is_valid_bit_depth = match header_info.color_type {
-            ColorType::Grayscale => matches!(header_info.bit_depth, 1 | 2 | 4 | 8 | 16),
-            ColorType::IndexedColor => matches!(header_info.bit_depth, 1 | 2 | 4 | 8),
-            ColorType::TrueColor | ColorType::GrayscaleAlpha | ColorType::TrueColorAlpha => {
-                matches!(header_info.bit_depth, 8 | 16)
-            }
-        };
-
-        if !is_valid_bit_depth {
-            return Err(ParserError::InvalidFieldValue);
-        }
-
-        self.header = Some(header_info);
-
-        return Ok(());
     }
 
-    fn parse_chunks(&mut self) -> Result<(), ParserError> {
-        while self.trailer.is_none() {
-            let length = utils::read_u32(self.source)?;
-            let type_and_data = utils::read_bytes(self.source, 4 + length as usize)?;
-            let crc = utils::read_u32(self.source)?;
-
-            if crc32::compute(&type_and_data) != crc {
-                return Err(ParserError::CorruptedData);
-            }
-
-            let chunk_type = utils::to_u32(&type_and_data[..4]);
-            let data = &type_and_data[4..];
+    /// Returns whether the trailer has been processed.
+    pub(crate) fn is_finished(&self) -> bool {
+        return self.trailer.is_some();
+    }
 
+    /// Validates the provided chunk against the chunks processed so far and records its info.
+    ///
+    /// # Arguments
+    /// * `chunk_type` - The chunk type.
+    /// * `data` - The chunk data, whose CRC is expected to be checked by the caller.
+    ///
+    /// # Returns
+    /// * `Result<ChunkDisposition, ParserError>` - How the chunk was kept, or the error that invalidates the stream.
+    pub(crate) fn process_chunk<'d>(
+        &mut self,
+        chunk_type: ChunkId,
+        data: &'d [u8],
+    ) -> Result<ChunkDisposition<'d>, ParserError> {
+        self.is_unknown = false;
+        let mut disposition = ChunkDisposition::Parsed;
+        let length = data.len() as u32;
+
+        if self.header.is_none() {
+            self.parse_header(chunk_type, data)?;
+        } else {
             match chunk_type {
                 chunk_ids::PLTE => self.parse_plte(length, data)?,
                 chunk_ids::IDAT => self.parse_idat(length, data)?,
@@ -166,24 +298,67 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
                 chunk_ids::cHRM => self.parse_chrm(length, data)?,
                 chunk_ids::sRGB => self.parse_srgb(length, data)?,
                 chunk_ids::iCCP => self.parse_iccp(length, data)?,
-                chunk_ids::tEXt => self.parse_text(length, data)?,
-                chunk_ids::zTXt => self.parse_ztxt(length, data)?,
-                chunk_ids::iTXt => self.parse_itxt(length, data)?,
+                chunk_ids::tEXt => disposition = ChunkDisposition::Text(self.parse_text(data)?),
+                chunk_ids::zTXt => {
+                    disposition = ChunkDisposition::CompressedText(self.parse_ztxt(data)?)
+                }
+                chunk_ids::iTXt => {
+                    disposition = ChunkDisposition::InternationalText(self.parse_itxt(data)?)
+                }
                 chunk_ids::bKGD => self.parse_bkgd(length, data)?,
                 chunk_ids::pHYs => self.parse_phys(length, data)?,
                 chunk_ids::sBIT => self.parse_sbit(length, data)?,
                 chunk_ids::sPLT => self.parse_splt(length, data)?,
                 chunk_ids::hIST => self.parse_hist(length, data)?,
                 chunk_ids::tIME => self.parse_time(length, data)?,
-                _ => {
-                    self.unknown_chunks.push(UnknownChunkInfo {
-                        chunk_type: chunk_type.to_be_bytes(),
-                        data: Vec::from(data),
-                    });
-                }
+                chunk_ids::oFFs => self.parse_offs(length, data)?,
+                chunk_ids::pCAL => self.parse_pcal(length, data)?,
+                chunk_ids::sCAL => self.parse_scal(length, data)?,
+                chunk_ids::sTER => self.parse_ster(length, data)?,
+                chunk_ids::gIFg => self.parse_gifg(length, data)?,
+                chunk_ids::gIFx => self.parse_gifx(length, data)?,
+                chunk_ids::gIFt => self.parse_gift(length, data)?,
+                chunk_ids::cICP => self.parse_cicp(length, data)?,
+                chunk_ids::mDCv => self.parse_mdcv(length, data)?,
+                chunk_ids::cLLi => self.parse_clli(length, data)?,
+                chunk_ids::eXIf => self.parse_exif(length, data)?,
+                chunk_ids::acTL => self.parse_actl(length, data)?,
+                chunk_ids::fcTL => self.parse_fctl(length, data)?,
+                chunk_ids::fdAT => self.parse_fdat(length, data)?,
+                _ => self.parse_unknown(chunk_type, data)?,
             };
         }
 
+        self.previous_chunk = Some(chunk_type);
+        self.chunk_order.push(chunk_type.to_be_bytes());
+
+        if self.is_unknown {
+            return Ok(ChunkDisposition::Unknown);
+        }
+
+        return Ok(disposition);
+    }
+
+    fn parse_header(&mut self, chunk_type: ChunkId, data: &[u8]) -> Result<(), ParserError> {
+        // Apple's optimized images have a marker chunk right before the header.
+        if chunk_type == chunk_ids::CgBI && self.previous_chunk.is_none() {
+            if data.len() != 4 {
+                return Err(ParserError::InvalidChunkLength(chunk_ids::CgBI));
+            }
+
+            self.cgbi = Some(CgBIInfo {
+                flags: utils::to_u32(data),
+            });
+
+            return Ok(());
+        }
+
+        if chunk_type != chunk_ids::IHDR {
+            return Err(ParserError::MissingRequiredChunk(chunk_ids::IHDR));
+        }
+
+        self.header = Some(read_header_info(data)?);
+
         return Ok(());
     }
 
@@ -194,6 +369,15 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
 
         self.check_chunk_order(chunk_ids::PLTE, Self::BEFORE_IDAT_CHUNK)?;
 
+        // The chunks that may come without a palette must still follow it when it is present.
+        if self.transparency.is_some() {
+            return Err(ParserError::InvalidChunkOrder(chunk_ids::tRNS));
+        }
+
+        if self.background.is_some() {
+            return Err(ParserError::InvalidChunkOrder(chunk_ids::bKGD));
+        }
+
         if length % 3 != 0 {
             return Err(ParserError::InvalidChunkLength(chunk_ids::PLTE));
         }
@@ -205,48 +389,100 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
         return Ok(());
     }
 
-    fn parse_idat(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
-        if !self.compressed_data.is_none() {
-            return Err(ParserError::NonConsecutiveData);
+    /// Returns the options the state was created with.
+    pub(crate) fn options(&self) -> Option<&'a ParserOptions> {
+        return self.options;
+    }
+
+    /// Returns the header, if it has been processed.
+    pub(crate) fn header(&self) -> Option<&HeaderInfo> {
+        return self.header.as_ref();
+    }
+
+    /// Returns the position of the current chunk relative to the palette and the image data.
+    pub(crate) fn chunk_position(&self) -> ChunkPosition {
+        if self.compressed_data.is_some() {
+            return ChunkPosition::AfterImageData;
         }
 
-        if length == 0 {
-            return Err(ParserError::InvalidChunkLength(chunk_ids::IDAT));
+        if self.palette.is_some() {
+            return ChunkPosition::BeforeImageData;
         }
 
-        let mut data = Vec::from(data);
-        let mut chunk_count = 1;
+        return ChunkPosition::BeforePalette;
+    }
 
-        loop {
-            let length = utils::read_u32(self.source)?;
-            let chunk_type = utils::read_u32(self.source)?;
+    /// Returns whether the image is an Apple optimized image, whose image data is a raw deflate stream.
+    pub(crate) fn is_cgbi(&self) -> bool {
+        return self.cgbi.is_some();
+    }
 
-            if chunk_type != chunk_ids::IDAT {
-                utils::seek(self.source, -8)?;
-                break;
-            }
+    /// Returns how the data of `IDAT` chunks is handled.
+    pub(crate) fn image_data_handling(&self) -> ImageDataHandling {
+        return match self.options {
+            Some(options) => options.image_data,
+            None => ImageDataHandling::default(),
+        };
+    }
 
-            if length == 0 {
-                return Err(ParserError::InvalidChunkLength(chunk_ids::IDAT));
-            }
+    /// Appends data of the current `IDAT` chunk that was validated through `process_image_data_span`.
+    pub(crate) fn append_image_data(&mut self, data: &[u8]) {
+        if let Some(compressed_data) = &mut self.compressed_data {
+            compressed_data.data.extend_from_slice(data);
+        }
+    }
 
-            utils::seek(self.source, -4)?;
-            let type_and_data = utils::read_bytes(self.source, 4 + length as usize)?;
-            let crc = utils::read_u32(self.source)?;
+    /// Validates an `IDAT` chunk whose data is left in the source, and records the position of its data.
+    pub(crate) fn process_image_data_span(&mut self, span: DataSpan) -> Result<(), ParserError> {
+        if self.header.is_none() {
+            return Err(ParserError::MissingRequiredChunk(chunk_ids::IHDR));
+        }
 
-            if crc32::compute(&type_and_data) != crc {
-                return Err(ParserError::CorruptedData);
-            }
+        self.add_image_data_chunk(span.length)?;
+        self.image_data_spans.push(span);
+        self.previous_chunk = Some(chunk_ids::IDAT);
+        self.chunk_order.push(chunk_ids::IDAT.to_be_bytes());
 
-            data.extend_from_slice(&type_and_data[4..]);
-            chunk_count += 1;
-        }
+        return Ok(());
+    }
 
-        self.compressed_data = Some(CompressedDataInfo { chunk_count, data });
+    fn parse_idat(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        let retain_payloads = self.retain_payloads;
+        let compressed_data = self.add_image_data_chunk(length)?;
+        if retain_payloads {
+            compressed_data.data.extend_from_slice(data);
+        }
 
         return Ok(());
     }
 
+    fn add_image_data_chunk(
+        &mut self,
+        length: u32,
+    ) -> Result<&mut CompressedDataInfo, ParserError> {
+        if length == 0 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::IDAT));
+        }
+
+        let compressed_data = self
+            .compressed_data
+            .get_or_insert_with(|| CompressedDataInfo {
+                chunk_count: 0,
+                chunk_lengths: Vec::new(),
+                data: Vec::new(),
+            });
+
+        // The image data may be split into several chunks, but they must not be interleaved with other chunks.
+        if compressed_data.chunk_count > 0 && self.previous_chunk != Some(chunk_ids::IDAT) {
+            return Err(ParserError::NonConsecutiveData);
+        }
+
+        compressed_data.chunk_count += 1;
+        compressed_data.chunk_lengths.push(length);
+
+        return Ok(compressed_data);
+    }
+
     fn parse_iend(&mut self, length: u32, _data: &[u8]) -> Result<(), ParserError> {
         if !self.trailer.is_none() {
             return Err(ParserError::DuplicateChunk(chunk_ids::IEND));
@@ -266,10 +502,13 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
             return Err(ParserError::DuplicateChunk(chunk_ids::tRNS));
         }
 
-        self.check_chunk_order(
-            chunk_ids::tRNS,
-            Self::AFTER_PLTE_CHUNK | Self::BEFORE_IDAT_CHUNK,
-        )?;
+        // The palette is optional for other color types, so this chunk must follow it only when it is required.
+        let constraint = match self.header.as_ref().unwrap().color_type {
+            ColorType::IndexedColor => Self::AFTER_PLTE_CHUNK | Self::BEFORE_IDAT_CHUNK,
+            _ => Self::BEFORE_IDAT_CHUNK,
+        };
+
+        self.check_chunk_order(chunk_ids::tRNS, constraint)?;
 
         let header = self.header.as_ref().unwrap();
 
@@ -294,7 +533,7 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
             }
             ColorType::IndexedColor => {
                 let palette = self.palette.as_ref().unwrap();
-                if length != palette.entries.len() as u32 {
+                if length > palette.entries.len() as u32 {
                     return Err(ParserError::InvalidChunkLength(chunk_ids::tRNS));
                 }
 
@@ -399,9 +638,11 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
             return Err(ParserError::InvalidChunkLength(chunk_ids::iCCP));
         }
 
-        let name = utils::get_string(data)?;
-        utils::validate_string(&name)?;
-        let data = &data[name.len()..];
+        let (name, data) = utils::split_keyword(data)?;
+        let name = utils::latin1_to_string(name);
+        if data.is_empty() {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::iCCP));
+        }
 
         let compression_method = match data[0] {
             0 => CompressionMethod::Deflate,
@@ -420,84 +661,41 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
         return Ok(());
     }
 
-    fn parse_text(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
-        if length < 2 {
-            return Err(ParserError::InvalidChunkLength(chunk_ids::tEXt));
+    fn parse_text<'d>(&mut self, data: &'d [u8]) -> Result<BorrowedTextualData<'d>, ParserError> {
+        let textual_data = BorrowedTextualData::read(data, self.chunk_position())?;
+        if self.retain_payloads {
+            self.textual_data.push(textual_data.to_info());
         }
 
-        let keyword = utils::get_string(data)?;
-        utils::validate_string(&keyword)?;
-        let data = &data[keyword.len() + 1..];
-
-        let text = utils::to_string(data);
-
-        self.textual_data.push(TextualDataInfo { keyword, text });
-
-        return Ok(());
+        return Ok(textual_data);
     }
 
-    fn parse_ztxt(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
-        if length < 3 {
-            return Err(ParserError::InvalidChunkLength(chunk_ids::zTXt));
+    fn parse_ztxt<'d>(
+        &mut self,
+        data: &'d [u8],
+    ) -> Result<BorrowedCompressedTextualData<'d>, ParserError> {
+        let compressed_textual_data =
+            BorrowedCompressedTextualData::read(data, self.chunk_position())?;
+        if self.retain_payloads {
+            self.compressed_textual_data
+                .push(compressed_textual_data.to_info());
         }
 
-        let keyword = utils::get_string(data)?;
-        utils::validate_string(&keyword)?;
-        let data = &data[keyword.len() + 1..];
-
-        let compression_method = match data[0] {
-            0 => CompressionMethod::Deflate,
-            _ => return Err(ParserError::InvalidFieldValue),
-        };
-        let data = &data[1..];
-
-        let text = Vec::from(data);
-
-        self.compressed_textual_data
-            .push(CompressedTextualDataInfo {
-                keyword,
-                compression_method,
-                text,
-            });
-
-        return Ok(());
+        return Ok(compressed_textual_data);
     }
 
-    fn parse_itxt(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
-        if length < 6 {
-            return Err(ParserError::InvalidChunkLength(chunk_ids::iTXt));
+    fn parse_itxt<'d>(
+        &mut self,
+        data: &'d [u8],
+    ) -> Result<BorrowedInternationalTextualData<'d>, ParserError> {
+        let international_textual_data =
+            BorrowedInternationalTextualData::read(data, self.chunk_position())?;
+        if self.retain_payloads {
+            self.international_textual_data
+                .push(international_textual_data.to_info());
         }
 
-        let keyword = utils::get_string(data)?;
-        utils::validate_string(&keyword)?;
-        let data = &data[keyword.len() + 1..];
-
-        let is_compressed = data[0] == 1;
-        let compression_method = match data[1] {
-            0 => CompressionMethod::Deflate,
-            _ => return Err(ParserError::InvalidFieldValue),
-        };
-        let data = &data[2..];
-
-        let language_tag = utils::get_string(data)?;
-        let data = &data[language_tag.len() + 1..];
-
-        let translated_keyword = utils::get_string(data)?;
-        let data = &data[translated_keyword.len() + 1..];
-
-        let text = Vec::from(data);
-
-        self.international_textual_data
-            .push(InternationalTextualDataInfo {
-                keyword,
-                is_compressed,
-                compression_method,
-                language_tag,
-                translated_keyword,
-                text,
-            });
-
-        return Ok(());
+        return Ok(international_textual_data);
     }
 
     fn parse_bkgd(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
@@ -505,10 +703,13 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
             return Err(ParserError::DuplicateChunk(chunk_ids::bKGD));
         }
 
-        self.check_chunk_order(
-            chunk_ids::bKGD,
-            Self::AFTER_PLTE_CHUNK | Self::BEFORE_IDAT_CHUNK,
-        )?;
+        // The palette is optional for other color types, so this chunk must follow it only when it is required.
+        let constraint = match self.header.as_ref().unwrap().color_type {
+            ColorType::IndexedColor => Self::AFTER_PLTE_CHUNK | Self::BEFORE_IDAT_CHUNK,
+            _ => Self::BEFORE_IDAT_CHUNK,
+        };
+
+        self.check_chunk_order(chunk_ids::bKGD, constraint)?;
 
         let header = self.header.as_ref().unwrap();
 
@@ -636,15 +837,17 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
             return Err(ParserError::InvalidChunkLength(chunk_ids::sPLT));
         }
 
-        let name = utils::get_string(data)?;
-        utils::validate_string(&name)?;
-        let data = &data[name.len() + 1..];
+        let (name, data) = utils::split_keyword(data)?;
+        let name = utils::latin1_to_string(name);
+        if data.is_empty() {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::sPLT));
+        }
 
         let sample_depth = data[0];
         let data = &data[1..];
 
         let entries = match sample_depth {
-            1 => {
+            8 => {
                 if data.len() % 6 != 0 {
                     return Err(ParserError::InvalidChunkLength(chunk_ids::sPLT));
                 }
@@ -657,7 +860,7 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
                     frequency: utils::to_u16(&c[4..6]),
                 })
             }
-            2 => {
+            16 => {
                 if data.len() % 10 != 0 {
                     return Err(ParserError::InvalidChunkLength(chunk_ids::sPLT));
                 }
@@ -733,6 +936,524 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
         return Ok(());
     }
 
+    fn parse_offs(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if !self.image_offset.is_none() {
+            return Err(ParserError::DuplicateChunk(chunk_ids::oFFs));
+        }
+
+        self.check_chunk_order(chunk_ids::oFFs, Self::BEFORE_IDAT_CHUNK)?;
+
+        if length != 9 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::oFFs));
+        }
+
+        let position = (
+            utils::to_u32(&data[0..4]) as i32,
+            utils::to_u32(&data[4..8]) as i32,
+        );
+        let unit = match data[8] {
+            0 => OffsetUnit::Pixel,
+            1 => OffsetUnit::Micrometer,
+            _ => return Err(ParserError::InvalidFieldValue),
+        };
+
+        self.image_offset = Some(ImageOffsetInfo { position, unit });
+
+        return Ok(());
+    }
+
+    fn parse_pcal(&mut self, _length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if !self.pixel_calibration.is_none() {
+            return Err(ParserError::DuplicateChunk(chunk_ids::pCAL));
+        }
+
+        self.check_chunk_order(chunk_ids::pCAL, Self::BEFORE_IDAT_CHUNK)?;
+
+        let (name, data) = utils::split_keyword(data)?;
+        let name = utils::latin1_to_string(name);
+        if data.len() < 11 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::pCAL));
+        }
+
+        let original_zero = utils::to_u32(&data[0..4]) as i32;
+        let original_max = utils::to_u32(&data[4..8]) as i32;
+        if original_zero == original_max {
+            return Err(ParserError::InvalidFieldValue);
+        }
+
+        let equation_type = match data[8] {
+            0 => EquationType::Linear,
+            1 => EquationType::Exponential,
+            2 => EquationType::ArbitraryExponential,
+            3 => EquationType::Hyperbolic,
+            _ => return Err(ParserError::InvalidFieldValue),
+        };
+
+        if data[9] as usize != equation_type.parameter_count() {
+            return Err(ParserError::InvalidFieldValue);
+        }
+
+        // The unit name is Latin-1 text that may be empty, such as `°C` or no unit for ratios.
+        let data = &data[10..];
+        let terminator = match data.iter().position(|&b| b == 0) {
+            Some(index) => index,
+            None => return Err(ParserError::MissingNullTerminator),
+        };
+        let unit_name = utils::latin1_to_string(&data[..terminator]);
+
+        // The parameters are separated by null characters, without one after the last parameter.
+        let parameters = data[terminator + 1..]
+            .split(|&b| b == 0)
+            .map(utils::parse_ascii_float)
+            .collect::<Result<Vec<f64>, ParserError>>()?;
+
+        if parameters.len() != equation_type.parameter_count() {
+            return Err(ParserError::InvalidFieldValue);
+        }
+
+        self.pixel_calibration = Some(PixelCalibrationInfo {
+            name,
+            original_zero,
+            original_max,
+            equation_type,
+            unit_name,
+            parameters,
+        });
+
+        return Ok(());
+    }
+
+    fn parse_scal(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if !self.physical_scale.is_none() {
+            return Err(ParserError::DuplicateChunk(chunk_ids::sCAL));
+        }
+
+        self.check_chunk_order(chunk_ids::sCAL, Self::BEFORE_IDAT_CHUNK)?;
+
+        if length < 4 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::sCAL));
+        }
+
+        let unit = match data[0] {
+            1 => ScaleUnit::Meter,
+            2 => ScaleUnit::Radian,
+            _ => return Err(ParserError::InvalidFieldValue),
+        };
+
+        let data = &data[1..];
+        let separator = match data.iter().position(|&b| b == 0) {
+            Some(index) => index,
+            None => return Err(ParserError::MissingNullTerminator),
+        };
+
+        let pixel_width = utils::parse_ascii_float(&data[..separator])?;
+        let pixel_height = utils::parse_ascii_float(&data[separator + 1..])?;
+
+        if pixel_width <= 0.0 || pixel_height <= 0.0 {
+            return Err(ParserError::InvalidFieldValue);
+        }
+
+        self.physical_scale = Some(PhysicalScaleInfo {
+            unit,
+            pixel_width,
+            pixel_height,
+        });
+
+        return Ok(());
+    }
+
+    fn parse_ster(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if !self.stereo_image.is_none() {
+            return Err(ParserError::DuplicateChunk(chunk_ids::sTER));
+        }
+
+        self.check_chunk_order(chunk_ids::sTER, Self::BEFORE_IDAT_CHUNK)?;
+
+        if length != 1 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::sTER));
+        }
+
+        let layout = match data[0] {
+            0 => StereoLayout::CrossFuse,
+            1 => StereoLayout::DivergingFuse,
+            _ => return Err(ParserError::InvalidFieldValue),
+        };
+
+        self.stereo_image = Some(StereoImageInfo { layout });
+
+        return Ok(());
+    }
+
+    fn parse_gifg(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        // Like the other GIF extension chunks, this chunk may appear multiple times and anywhere in the stream.
+        if length != 4 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::gIFg));
+        }
+
+        let user_input = match data[1] {
+            0 => false,
+            1 => true,
+            _ => return Err(ParserError::InvalidFieldValue),
+        };
+
+        self.gif_graphic_controls.push(GifGraphicControlInfo {
+            disposal_method: data[0],
+            user_input,
+            delay_time: utils::to_u16(&data[2..4]),
+        });
+
+        return Ok(());
+    }
+
+    fn parse_gifx(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if length < 11 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::gIFx));
+        }
+
+        self.gif_application_extensions
+            .push(GifApplicationExtensionInfo {
+                application_identifier: data[0..8].try_into().unwrap(),
+                authentication_code: data[8..11].try_into().unwrap(),
+                data: Vec::from(&data[11..]),
+            });
+
+        return Ok(());
+    }
+
+    fn parse_gift(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if length < 24 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::gIFt));
+        }
+
+        self.gif_plain_text_extensions
+            .push(GifPlainTextExtensionInfo {
+                grid_position: (
+                    utils::to_u32(&data[0..4]) as i32,
+                    utils::to_u32(&data[4..8]) as i32,
+                ),
+                grid_size: (utils::to_u32(&data[8..12]), utils::to_u32(&data[12..16])),
+                cell_size: (data[16], data[17]),
+                foreground: (data[18], data[19], data[20]),
+                background: (data[21], data[22], data[23]),
+                text: utils::latin1_to_string(&data[24..]),
+            });
+
+        return Ok(());
+    }
+
+    fn parse_cicp(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if !self.coding_independent_code_points.is_none() {
+            return Err(ParserError::DuplicateChunk(chunk_ids::cICP));
+        }
+
+        self.check_chunk_order(
+            chunk_ids::cICP,
+            Self::BEFORE_PLTE_CHUNK | Self::BEFORE_IDAT_CHUNK,
+        )?;
+
+        if length != 4 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::cICP));
+        }
+
+        // PNG only stores RGB samples, so the identity matrix is the only allowed one.
+        if data[2] != 0 {
+            return Err(ParserError::InvalidFieldValue);
+        }
+
+        let is_full_range = match data[3] {
+            0 => false,
+            1 => true,
+            _ => return Err(ParserError::InvalidFieldValue),
+        };
+
+        self.coding_independent_code_points = Some(CodingIndependentCodePointsInfo {
+            color_primaries: data[0],
+            transfer_function: data[1],
+            matrix_coefficients: data[2],
+            is_full_range,
+        });
+
+        return Ok(());
+    }
+
+    fn parse_mdcv(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if !self.mastering_display_color_volume.is_none() {
+            return Err(ParserError::DuplicateChunk(chunk_ids::mDCv));
+        }
+
+        self.check_chunk_order(
+            chunk_ids::mDCv,
+            Self::BEFORE_PLTE_CHUNK | Self::BEFORE_IDAT_CHUNK,
+        )?;
+
+        if length != 24 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::mDCv));
+        }
+
+        // The chromaticities are in units of 0.00002 and the luminances are in units of 0.0001 candelas.
+        let point = |offset: usize| {
+            (
+                utils::to_u16(&data[offset..offset + 2]) as f32 / 50000.0,
+                utils::to_u16(&data[offset + 2..offset + 4]) as f32 / 50000.0,
+            )
+        };
+
+        let max_luminance = utils::to_u32(&data[16..20]) as f32 / 10000.0;
+        let min_luminance = utils::to_u32(&data[20..24]) as f32 / 10000.0;
+
+        // Either luminance may be 0 when it is unknown, so the range is only checked when both are present.
+        if max_luminance != 0.0 && min_luminance != 0.0 && min_luminance > max_luminance {
+            return Err(ParserError::InvalidFieldValue);
+        }
+
+        self.mastering_display_color_volume = Some(MasteringDisplayColorVolumeInfo {
+            red: point(0),
+            green: point(4),
+            blue: point(8),
+            white_point: point(12),
+            max_luminance,
+            min_luminance,
+        });
+
+        return Ok(());
+    }
+
+    fn parse_clli(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if !self.content_light_level.is_none() {
+            return Err(ParserError::DuplicateChunk(chunk_ids::cLLi));
+        }
+
+        self.check_chunk_order(
+            chunk_ids::cLLi,
+            Self::BEFORE_PLTE_CHUNK | Self::BEFORE_IDAT_CHUNK,
+        )?;
+
+        if length != 8 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::cLLi));
+        }
+
+        self.content_light_level = Some(ContentLightLevelInfo {
+            max_content_light_level: utils::to_u32(&data[0..4]) as f32 / 10000.0,
+            max_frame_average_light_level: utils::to_u32(&data[4..8]) as f32 / 10000.0,
+        });
+
+        return Ok(());
+    }
+
+    fn parse_exif(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if !self.exif.is_none() {
+            return Err(ParserError::DuplicateChunk(chunk_ids::eXIf));
+        }
+
+        self.check_chunk_order(chunk_ids::eXIf, Self::BEFORE_IDAT_CHUNK)?;
+
+        if length < 8 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::eXIf));
+        }
+
+        // The profile is kept even if it cannot be read, since it is only metadata and is written back as it is.
+        self.exif = Some(ExifInfo {
+            data: Vec::from(data),
+            metadata: exif::read(data).ok(),
+        });
+
+        return Ok(());
+    }
+
+    fn parse_actl(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if !self.animation_control.is_none() {
+            return Err(ParserError::DuplicateChunk(chunk_ids::acTL));
+        }
+
+        self.check_chunk_order(chunk_ids::acTL, Self::BEFORE_IDAT_CHUNK)?;
+
+        if length != 8 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::acTL));
+        }
+
+        let frame_count = utils::to_u32(&data[0..4]);
+        let play_count = utils::to_u32(&data[4..8]);
+
+        if frame_count == 0 {
+            return Err(ParserError::InvalidFieldValue);
+        }
+
+        self.animation_control = Some(AnimationControlInfo {
+            frame_count,
+            play_count,
+        });
+
+        return Ok(());
+    }
+
+    fn parse_fctl(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        // Without an animation control, the image is a static PNG and the frame chunks are not interpreted.
+        let Some(animation_control) = self.animation_control else {
+            self.push_unknown_chunk(chunk_ids::fcTL, data);
+            return Ok(());
+        };
+
+        if length != 26 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::fcTL));
+        }
+
+        self.check_sequence_number(chunk_ids::fcTL, data)?;
+
+        let control = FrameControlInfo {
+            sequence_number: utils::to_u32(&data[0..4]),
+            width: utils::to_u32(&data[4..8]),
+            height: utils::to_u32(&data[8..12]),
+            x_offset: utils::to_u32(&data[12..16]),
+            y_offset: utils::to_u32(&data[16..20]),
+            delay_numerator: utils::to_u16(&data[20..22]),
+            delay_denominator: utils::to_u16(&data[22..24]),
+            dispose_op: match data[24] {
+                0 => DisposeOp::None,
+                1 => DisposeOp::Background,
+                2 => DisposeOp::Previous,
+                _ => return Err(ParserError::InvalidFieldValue),
+            },
+            blend_op: match data[25] {
+                0 => BlendOp::Source,
+                1 => BlendOp::Over,
+                _ => return Err(ParserError::InvalidFieldValue),
+            },
+        };
+
+        let header = self.header.as_ref().unwrap();
+        if control.width == 0
+            || control.height == 0
+            || control.x_offset as u64 + control.width as u64 > header.width as u64
+            || control.y_offset as u64 + control.height as u64 > header.height as u64
+        {
+            return Err(ParserError::InvalidFieldValue);
+        }
+
+        if self.animation_frames.len() >= animation_control.frame_count as usize {
+            return Err(ParserError::InvalidFieldValue);
+        }
+
+        let data = match self.compressed_data {
+            // The only frame control before the image data describes the default image, which must cover the whole canvas.
+            None => {
+                if !self.animation_frames.is_empty() {
+                    return Err(ParserError::InvalidChunkOrder(chunk_ids::fcTL));
+                }
+
+                if control.x_offset != 0
+                    || control.y_offset != 0
+                    || control.width != header.width
+                    || control.height != header.height
+                {
+                    return Err(ParserError::InvalidFieldValue);
+                }
+
+                None
+            }
+            Some(_) => {
+                self.check_frame_data()?;
+                Some(FrameDataInfo {
+                    chunk_count: 0,
+                    chunk_lengths: Vec::new(),
+                    data: Vec::new(),
+                })
+            }
+        };
+
+        self.animation_frames.push(FrameInfo { control, data });
+
+        return Ok(());
+    }
+
+    fn parse_fdat(&mut self, length: u32, data: &[u8]) -> Result<(), ParserError> {
+        if self.animation_control.is_none() {
+            self.push_unknown_chunk(chunk_ids::fdAT, data);
+            return Ok(());
+        }
+
+        if length < 5 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::fdAT));
+        }
+
+        self.check_sequence_number(chunk_ids::fdAT, data)?;
+
+        let frame_data = match self.animation_frames.last_mut() {
+            Some(FrameInfo {
+                data: Some(frame_data),
+                ..
+            }) => frame_data,
+            _ => return Err(ParserError::InvalidChunkOrder(chunk_ids::fdAT)),
+        };
+
+        frame_data.data.extend_from_slice(&data[4..]);
+        frame_data.chunk_count += 1;
+        frame_data.chunk_lengths.push(length - 4);
+
+        return Ok(());
+    }
+
+    fn parse_unknown(&mut self, chunk_type: ChunkId, data: &[u8]) -> Result<(), ParserError> {
+        let Some(handler) = self
+            .options
+            .and_then(|options| options.handlers.get(&chunk_type))
+        else {
+            self.push_unknown_chunk(chunk_type, data);
+            return Ok(());
+        };
+
+        let context = ChunkContext {
+            header: self.header.as_ref().unwrap(),
+            has_palette: self.palette.is_some(),
+            has_image_data: self.compressed_data.is_some(),
+        };
+
+        self.custom_chunks.push(CustomChunkInfo {
+            chunk_type: chunk_type.to_be_bytes(),
+            data: Vec::from(data),
+            position: self.chunk_position(),
+            unknown_chunk_index: self.unknown_chunk_count,
+            value: handler.handle_dyn(&context, data)?,
+        });
+
+        return Ok(());
+    }
+
+    fn push_unknown_chunk(&mut self, chunk_type: ChunkId, data: &[u8]) {
+        self.is_unknown = true;
+        self.unknown_chunk_count += 1;
+        if self.retain_payloads {
+            self.unknown_chunks.push(UnknownChunkInfo {
+                chunk_type: chunk_type.to_be_bytes(),
+                data: Vec::from(data),
+                position: self.chunk_position(),
+            });
+        }
+    }
+
+    fn check_sequence_number(&mut self, chunk_id: ChunkId, data: &[u8]) -> Result<(), ParserError> {
+        if utils::to_u32(&data[0..4]) != self.sequence_number {
+            return Err(ParserError::InvalidSequenceNumber(chunk_id));
+        }
+
+        self.sequence_number += 1;
+
+        return Ok(());
+    }
+
+    /// Checks whether the last frame, if it is not the default image, has received its data.
+    fn check_frame_data(&self) -> Result<(), ParserError> {
+        if let Some(FrameInfo {
+            data: Some(frame_data),
+            ..
+        }) = self.animation_frames.last()
+            && frame_data.chunk_count == 0
+        {
+            return Err(ParserError::MissingRequiredChunk(chunk_ids::fdAT));
+        }
+
+        return Ok(());
+    }
+
     fn check_chunk_order(&self, chunk_id: ChunkId, constraint: u8) -> Result<(), ParserError> {
         if (constraint & Self::BEFORE_PLTE_CHUNK) != 0 && !self.palette.is_none() {
             return Err(ParserError::InvalidChunkOrder(chunk_id));
@@ -749,7 +1470,20 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
         return Ok(());
     }
 
-    fn collect(self) -> Result<PngInfo, ParserError> {
+    /// Checks that the required chunks have been processed and turns the state into the info of the image.
+    pub(crate) fn collect(self) -> Result<PngInfo, ParserError> {
+        if self.header.is_none() {
+            return Err(ParserError::MissingRequiredChunk(chunk_ids::IHDR));
+        }
+
+        if let Some(animation_control) = &self.animation_control {
+            self.check_frame_data()?;
+
+            if self.animation_frames.len() != animation_control.frame_count as usize {
+                return Err(ParserError::MissingRequiredChunk(chunk_ids::fcTL));
+            }
+        }
+
         let header = self.header.unwrap();
         let palette = match self.palette {
             Some(palette) => Some(palette),
@@ -775,8 +1509,10 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
         };
 
         return Ok(PngInfo {
+            cgbi: self.cgbi,
             header,
             compressed_data,
+            image_data_spans: self.image_data_spans,
             palette,
             trailer,
             transparency: self.transparency,
@@ -793,7 +1529,167 @@ impl<'a, Source: Read + Seek> Parser<'a, Source> {
             suggested_palettes: self.suggested_palettes,
             palette_histogram: self.palette_histogram,
             last_modification: self.last_modification,
+            image_offset: self.image_offset,
+            pixel_calibration: self.pixel_calibration,
+            physical_scale: self.physical_scale,
+            stereo_image: self.stereo_image,
+            gif_graphic_controls: self.gif_graphic_controls,
+            gif_application_extensions: self.gif_application_extensions,
+            gif_plain_text_extensions: self.gif_plain_text_extensions,
+            coding_independent_code_points: self.coding_independent_code_points,
+            mastering_display_color_volume: self.mastering_display_color_volume,
+            content_light_level: self.content_light_level,
+            exif: self.exif,
             unknown_chunks: self.unknown_chunks,
+            custom_chunks: self.custom_chunks,
+            animation_control: self.animation_control,
+            animation_frames: self.animation_frames,
+            chunk_order: self.chunk_order,
+        });
+    }
+}
+
+/// Reads the header info out of the data of an `IHDR` chunk and validates its fields.
+pub(crate) fn read_header_info(data: &[u8]) -> Result<HeaderInfo, ParserError> {
+    if data.len() != 13 {
+        return Err(ParserError::InvalidChunkLength(chunk_ids::IHDR));
+    }
+
+    let header_info = HeaderInfo {
+        width: utils::to_u32(&data[0..4]),
+        height: utils::to_u32(&data[4..8]),
+        bit_depth: data[8],
+        color_type: match data[9] {
+            0 => ColorType::Grayscale,
+            2 => ColorType::TrueColor,
+            3 => ColorType::IndexedColor,
+            4 => ColorType::GrayscaleAlpha,
+            6 => ColorType::TrueColorAlpha,
+            _ => return Err(ParserError::InvalidFieldValue),
+        },
+        compression_method: match data[10] {
+            0 => CompressionMethod::Deflate,
+            _ => return Err(ParserError::InvalidFieldValue),
+        },
+        filter_method: match data[11] {
+            0 => FilterMethod::Adaptive,
+            _ => return Err(ParserError::InvalidFieldValue),
+        },
+        interlace_method: match data[12] {
+            0 => InterlaceMethod::None,
+            1 => InterlaceMethod::Adam7,
+            _ => return Err(ParserError::InvalidFieldValue),
+        },
+    };
+
+    if header_info.width == 0 || header_info.height == 0 {
+        return Err(ParserError::InvalidFieldValue);
+    }
+
+    if !header_info.has_valid_bit_depth() {
+        return Err(ParserError::InvalidFieldValue);
+    }
+
+    return Ok(header_info);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::deflate::{self, CompressionLevel};
+    use crate::test_utils::{Stream, chunk, split_png};
+    use crate::writer::Writer;
+
+    #[test]
+    fn test_parse_non_seekable() {
+        let png = split_png();
+        let info = Parser::parse(&mut Stream::new(&png)).unwrap();
+        let expected = Parser::parse(&mut Cursor::new(&png)).unwrap();
+
+        assert_eq!(info.compressed_data.chunk_count, 3);
+        assert_eq!(info.compressed_data.data, expected.compressed_data.data);
+    }
+
+    #[test]
+    fn test_parse_signature_only() {
+        // The source ends cleanly right after the signature, before any chunk.
+        assert!(matches!(
+            Parser::parse(&mut Cursor::new(&SIGNATURE)),
+            Err(ParserError::MissingRequiredChunk(chunk_ids::IHDR))
+        ));
+        assert!(matches!(
+            Parser::parse(&mut Stream::new(&SIGNATURE)),
+            Err(ParserError::MissingRequiredChunk(chunk_ids::IHDR))
+        ));
+    }
+
+    #[test]
+    fn test_parse_non_consecutive_data() {
+        let mut png = split_png();
+        let chunks: Vec<Chunk> = ChunkReader::new(&mut Cursor::new(&png))
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect();
+
+        // Moves a text chunk between the first and the second image data chunks.
+        let position = chunks[2].offset as usize;
+        png.splice(position..position, chunk(b"tEXt", b"Comment\0split"));
+
+        assert!(matches!(
+            Parser::parse(&mut Stream::new(&png)),
+            Err(ParserError::NonConsecutiveData)
+        ));
+    }
+
+    #[test]
+    fn test_parse_background_before_palette() {
+        let mut info = PngInfo::new(
+            HeaderInfo {
+                width: 1,
+                height: 1,
+                bit_depth: 8,
+                color_type: ColorType::TrueColor,
+                compression_method: CompressionMethod::Deflate,
+                filter_method: FilterMethod::Adaptive,
+                interlace_method: InterlaceMethod::None,
+            },
+            CompressedDataInfo {
+                chunk_count: 1,
+                chunk_lengths: Vec::new(),
+                data: deflate::compress(&[0, 1, 2, 3], CompressionLevel::Default),
+            },
+        );
+        info.palette = Some(PaletteInfo {
+            entries: vec![(1, 2, 3)],
+        });
+        info.background = Some(BackgroundInfo {
+            background: BackgroundVariant::TrueColor(1, 2, 3),
         });
+
+        let mut png = Vec::new();
+        Writer::write(&mut png, &info).unwrap();
+        assert!(Parser::parse(&mut Cursor::new(&png)).is_ok());
+
+        // Moves the background chunk before the palette, which it must follow even if the palette is optional.
+        let chunks: Vec<Chunk> = ChunkReader::new(&mut Cursor::new(&png))
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect();
+        let palette = &chunks[1];
+        let background = &chunks[2];
+        assert_eq!(palette.chunk_type, chunk_ids::PLTE);
+        assert_eq!(background.chunk_type, chunk_ids::bKGD);
+
+        let start = palette.offset as usize;
+        let end = background.offset as usize + 12 + background.length as usize;
+        png[start..end].rotate_left(background.offset as usize - start);
+
+        assert!(matches!(
+            Parser::parse(&mut Cursor::new(&png)),
+            Err(ParserError::InvalidChunkOrder(chunk_ids::bKGD))
+        ));
     }
 }
@@ -0,0 +1,134 @@
+use std::io::Read;
+
+use crate::crc32;
+use crate::error::ParserError;
+use crate::parser;
+use crate::spec::*;
+use crate::utils;
+
+/// Represents the result of probing an image for its header and animation.
+#[derive(Debug, Clone)]
+pub struct ProbeInfo {
+    /// The header.
+    pub header: HeaderInfo,
+    /// Whether the image has an animation control, which makes it an APNG.
+    pub is_animated: bool,
+}
+
+/// Reads only the signature and the header of an image, without requiring the source to be seekable.
+///
+/// Exactly 33 bytes are read from the source, or 49 bytes for Apple optimized images.
+///
+/// # Arguments
+/// * `source` - The source to read the image from.
+///
+/// # Returns
+/// * `HeaderInfo` - The validated header.
+pub fn probe<Source: Read>(source: &mut Source) -> Result<HeaderInfo, ParserError> {
+    let mut signature = [0u8; 8];
+    utils::read_to(source, &mut signature)?;
+    if signature != SIGNATURE {
+        return Err(ParserError::InvalidSignature);
+    }
+
+    let (mut chunk_type, mut data) = read_small_chunk(source)?;
+
+    // Apple's optimized images have a marker chunk right before the header.
+    if chunk_type == chunk_ids::CgBI {
+        (chunk_type, data) = read_small_chunk(source)?;
+    }
+
+    if chunk_type != chunk_ids::IHDR {
+        return Err(ParserError::MissingRequiredChunk(chunk_ids::IHDR));
+    }
+
+    return parser::read_header_info(&data);
+}
+
+/// Reads the signature and the header of an image, then skips the chunks until the animation control or the image data.
+///
+/// The skipped chunks are neither buffered nor checked, so only the header is validated.
+///
+/// # Arguments
+/// * `source` - The source to read the image from.
+///
+/// # Returns
+/// * `ProbeInfo` - The validated header and whether the image is animated.
+pub fn probe_animation<Source: Read>(source: &mut Source) -> Result<ProbeInfo, ParserError> {
+    let header = probe(source)?;
+
+    // The animation control must come before the image data, so the search ends there.
+    let is_animated = loop {
+        let length = utils::read_u32(source)?;
+        let chunk_type = utils::read_u32(source)?;
+
+        match chunk_type {
+            chunk_ids::acTL => break true,
+            chunk_ids::IDAT | chunk_ids::IEND => break false,
+            _ => utils::skip(source, length as u64 + 4)?,
+        }
+    };
+
+    return Ok(ProbeInfo {
+        header,
+        is_animated,
+    });
+}
+
+/// Reads a chunk that is expected before the header, whose length is limited to avoid large allocations.
+fn read_small_chunk<Source: Read>(source: &mut Source) -> Result<(ChunkId, Vec<u8>), ParserError> {
+    let length = utils::read_u32(source)? as usize;
+    let mut type_and_data = [0u8; 4 + 13];
+    utils::read_to(source, &mut type_and_data[..4])?;
+    let chunk_type = utils::to_u32(&type_and_data[..4]);
+
+    if length > 13 {
+        return Err(ParserError::InvalidChunkLength(chunk_type));
+    }
+
+    utils::read_to(source, &mut type_and_data[4..4 + length])?;
+    let crc = utils::read_u32(source)?;
+    if crc32::compute(&type_and_data[..4 + length]) != crc {
+        return Err(ParserError::CorruptedData);
+    }
+
+    return Ok((chunk_type, Vec::from(&type_and_data[4..4 + length])));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::{self, AnimationFrame};
+    use crate::test_utils::Stream;
+
+    #[test]
+    fn test_probe_reads_only_header() {
+        let pixels = [255u8; 3 * 2 * 4];
+        let frames = [AnimationFrame {
+            pixels: &pixels,
+            delay_numerator: 1,
+            delay_denominator: 10,
+        }];
+
+        let mut output = Vec::new();
+        encoder::encode_animation(&mut output, 3, 2, &frames, 0, &Default::default()).unwrap();
+
+        let mut stream = Stream::new(&output);
+        let header = probe(&mut stream).unwrap();
+        assert_eq!((header.width, header.height), (3, 2));
+        assert_eq!(header.color_type, ColorType::TrueColorAlpha);
+        assert_eq!(stream.read, 33);
+
+        let mut stream = Stream::new(&output);
+        assert!(probe_animation(&mut stream).unwrap().is_animated);
+    }
+
+    #[test]
+    fn test_probe_invalid_signature() {
+        let mut stream = Stream::new(b"GIF89a\0\0");
+        assert!(matches!(
+            probe(&mut stream),
+            Err(ParserError::InvalidSignature)
+        ));
+    }
+}
@@ -0,0 +1,475 @@
+use std::borrow::Cow;
+use std::io::ErrorKind;
+
+use crate::chunk::MAX_CHUNK_LENGTH;
+use crate::crc32;
+use crate::error::ParserError;
+use crate::parser::{ChunkDisposition, ParserOptions, ParserState};
+use crate::spec::*;
+use crate::utils;
+
+/// Represents the info of `tEXt` chunk that borrows from the parsed data.
+#[derive(Debug, Clone)]
+pub struct BorrowedTextualData<'a> {
+    /// The keyword, borrowed unless it has non-ASCII Latin-1 characters.
+    pub keyword: Cow<'a, str>,
+    /// The text, borrowed unless it has non-ASCII Latin-1 characters.
+    pub text: Cow<'a, str>,
+    /// The position of the chunk in the stream.
+    pub position: ChunkPosition,
+}
+
+impl<'a> BorrowedTextualData<'a> {
+    /// Reads and validates the data of a `tEXt` chunk.
+    pub(crate) fn read(data: &'a [u8], position: ChunkPosition) -> Result<Self, ParserError> {
+        if data.len() < 2 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::tEXt));
+        }
+
+        let (keyword, text) = utils::split_keyword(data)?;
+
+        return Ok(Self {
+            keyword: utils::latin1_to_str(keyword),
+            text: utils::latin1_to_str(text),
+            position,
+        });
+    }
+
+    /// Copies the chunk into its owned info.
+    pub fn to_info(&self) -> TextualDataInfo {
+        return TextualDataInfo {
+            keyword: self.keyword.to_string(),
+            text: self.text.to_string(),
+            position: self.position,
+        };
+    }
+}
+
+/// Represents the info of `zTXt` chunk that borrows from the parsed data.
+#[derive(Debug, Clone)]
+pub struct BorrowedCompressedTextualData<'a> {
+    /// The keyword, borrowed unless it has non-ASCII Latin-1 characters.
+    pub keyword: Cow<'a, str>,
+    /// The compression method used to compress text.
+    pub compression_method: CompressionMethod,
+    /// The compressed text data.
+    pub text: &'a [u8],
+    /// The position of the chunk in the stream.
+    pub position: ChunkPosition,
+}
+
+impl<'a> BorrowedCompressedTextualData<'a> {
+    /// Reads and validates the data of a `zTXt` chunk.
+    pub(crate) fn read(data: &'a [u8], position: ChunkPosition) -> Result<Self, ParserError> {
+        let (keyword, data) = utils::split_keyword(data)?;
+        if data.is_empty() {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::zTXt));
+        }
+
+        let compression_method = match data[0] {
+            0 => CompressionMethod::Deflate,
+            _ => return Err(ParserError::InvalidFieldValue),
+        };
+
+        return Ok(Self {
+            keyword: utils::latin1_to_str(keyword),
+            compression_method,
+            text: &data[1..],
+            position,
+        });
+    }
+
+    /// Copies the chunk into its owned info.
+    pub fn to_info(&self) -> CompressedTextualDataInfo {
+        return CompressedTextualDataInfo {
+            keyword: self.keyword.to_string(),
+            compression_method: self.compression_method,
+            text: Vec::from(self.text),
+            position: self.position,
+        };
+    }
+}
+
+/// Represents the info of `iTXt` chunk that borrows from the parsed data.
+#[derive(Debug, Clone)]
+pub struct BorrowedInternationalTextualData<'a> {
+    /// The keyword, borrowed unless it has non-ASCII Latin-1 characters.
+    pub keyword: Cow<'a, str>,
+    /// Whether the text is compressed.
+    pub is_compressed: bool,
+    /// The compression method used to compress text.
+    pub compression_method: CompressionMethod,
+    /// The language, borrowed unless it has non-ASCII characters.
+    pub language_tag: Cow<'a, str>,
+    /// The translated keyword.
+    pub translated_keyword: &'a str,
+    /// The (maybe compressed) UTF-8 text data.
+    pub text: &'a [u8],
+    /// The position of the chunk in the stream.
+    pub position: ChunkPosition,
+}
+
+impl<'a> BorrowedInternationalTextualData<'a> {
+    /// Reads and validates the data of an `iTXt` chunk.
+    pub(crate) fn read(data: &'a [u8], position: ChunkPosition) -> Result<Self, ParserError> {
+        let (keyword, data) = utils::split_keyword(data)?;
+        if data.len() < 2 {
+            return Err(ParserError::InvalidChunkLength(chunk_ids::iTXt));
+        }
+
+        let is_compressed = data[0] == 1;
+        let compression_method = match data[1] {
+            0 => CompressionMethod::Deflate,
+            _ => return Err(ParserError::InvalidFieldValue),
+        };
+
+        let (language_tag, data) = utils::split_null(&data[2..])?;
+        let (translated_keyword, text) = utils::split_null(data)?;
+        let translated_keyword =
+            str::from_utf8(translated_keyword).map_err(|_| ParserError::InvalidTextEncoding)?;
+
+        return Ok(Self {
+            keyword: utils::latin1_to_str(keyword),
+            is_compressed,
+            compression_method,
+            language_tag: utils::latin1_to_str(language_tag),
+            translated_keyword,
+            text,
+            position,
+        });
+    }
+
+    /// Copies the chunk into its owned info.
+    pub fn to_info(&self) -> InternationalTextualDataInfo {
+        return InternationalTextualDataInfo {
+            keyword: self.keyword.to_string(),
+            is_compressed: self.is_compressed,
+            compression_method: self.compression_method,
+            language_tag: self.language_tag.to_string(),
+            translated_keyword: self.translated_keyword.to_string(),
+            text: Vec::from(self.text),
+            position: self.position,
+        };
+    }
+}
+
+/// Represents an unknown chunk that borrows from the parsed data.
+#[derive(Debug, Clone, Copy)]
+pub struct BorrowedChunk<'a> {
+    /// The chunk type.
+    pub chunk_type: [u8; 4],
+    /// The raw data.
+    pub data: &'a [u8],
+    /// The position of the chunk in the stream.
+    pub position: ChunkPosition,
+}
+
+/// Represents the info of a PNG image whose image data, palette, textual and unknown chunks borrow from the parsed data.
+#[derive(Debug)]
+pub struct BorrowedPngInfo<'a> {
+    /// The info of the other chunks.
+    ///
+    /// Its compressed data has the chunk count but no data, and its textual and unknown chunks are empty.
+    /// Its palette is kept since validating the other chunks requires it.
+    pub info: PngInfo,
+    /// The palette colors in the form of RGB triplets.
+    pub palette: Option<&'a [[u8; 3]]>,
+    /// The data of each `IDAT` chunk in order, which form the compressed image data when concatenated.
+    pub image_data: Vec<&'a [u8]>,
+    /// The vector of textual data.
+    pub textual_data: Vec<BorrowedTextualData<'a>>,
+    /// The vector of compressed textual data.
+    pub compressed_textual_data: Vec<BorrowedCompressedTextualData<'a>>,
+    /// The vector of international textual data.
+    pub international_textual_data: Vec<BorrowedInternationalTextualData<'a>>,
+    /// The unidentified chunks.
+    pub unknown_chunks: Vec<BorrowedChunk<'a>>,
+}
+
+impl<'a> BorrowedPngInfo<'a> {
+    /// Returns the compressed image data, which is only copied when it is split into several chunks.
+    pub fn compressed_data(&self) -> Cow<'a, [u8]> {
+        return match self.image_data.as_slice() {
+            [data] => Cow::Borrowed(data),
+            segments => Cow::Owned(segments.concat()),
+        };
+    }
+
+    /// Copies the borrowed chunks into the info, which makes it usable for decoding and writing.
+    pub fn into_info(self) -> PngInfo {
+        let mut info = self.info;
+        info.compressed_data.data = self.image_data.concat();
+        info.textual_data = self
+            .textual_data
+            .iter()
+            .map(|text| text.to_info())
+            .collect();
+        info.compressed_textual_data = self
+            .compressed_textual_data
+            .iter()
+            .map(|text| text.to_info())
+            .collect();
+        info.international_textual_data = self
+            .international_textual_data
+            .iter()
+            .map(|text| text.to_info())
+            .collect();
+        info.unknown_chunks = self
+            .unknown_chunks
+            .iter()
+            .map(|chunk| UnknownChunkInfo {
+                chunk_type: chunk.chunk_type,
+                data: Vec::from(chunk.data),
+                position: chunk.position,
+            })
+            .collect();
+
+        return info;
+    }
+}
+
+/// Tries to parse PNG data from the provided buffer without copying its larger chunks.
+///
+/// # Arguments
+/// * `data` - The buffer that holds the PNG data.
+///
+/// # Returns
+/// * `Result<BorrowedPngInfo, ParserError>` - The parsed info borrowing from the buffer, or the error that stopped the parsing.
+pub fn parse_slice(data: &[u8]) -> Result<BorrowedPngInfo<'_>, ParserError> {
+    return parse_slice_with_options(data, &ParserOptions::default());
+}
+
+/// Tries to parse PNG data from the provided buffer with the provided options without copying its larger chunks.
+///
+/// # Arguments
+/// * `data` - The buffer that holds the PNG data.
+/// * `options` - The options that customize the parsing.
+///
+/// # Returns
+/// * `Result<BorrowedPngInfo, ParserError>` - The parsed info borrowing from the buffer, or the error that stopped the parsing.
+pub fn parse_slice_with_options<'a>(
+    data: &'a [u8],
+    options: &ParserOptions,
+) -> Result<BorrowedPngInfo<'a>, ParserError> {
+    if data.len() < SIGNATURE.len() {
+        return Err(ParserError::IOError(ErrorKind::UnexpectedEof.into()));
+    }
+
+    if data[..SIGNATURE.len()] != SIGNATURE {
+        return Err(ParserError::InvalidSignature);
+    }
+
+    let mut state = ParserState::without_payloads(options);
+    let mut palette = None;
+    let mut image_data = Vec::new();
+    let mut textual_data = Vec::new();
+    let mut compressed_textual_data = Vec::new();
+    let mut international_textual_data = Vec::new();
+    let mut unknown_chunks = Vec::new();
+
+    let mut position = SIGNATURE.len();
+
+    // Running out of data at a chunk boundary leaves the trailer missing, which the state reports when collecting.
+    while !state.is_finished() && position < data.len() {
+        let (chunk_type, chunk_data) = split_chunk(data, &mut position)?;
+        let chunk_position = state.chunk_position();
+
+        match state.process_chunk(chunk_type, chunk_data)? {
+            ChunkDisposition::Unknown => unknown_chunks.push(BorrowedChunk {
+                chunk_type: chunk_type.to_be_bytes(),
+                data: chunk_data,
+                position: chunk_position,
+            }),
+            ChunkDisposition::Parsed => match chunk_type {
+                chunk_ids::PLTE => palette = Some(chunk_data.as_chunks::<3>().0),
+                chunk_ids::IDAT => image_data.push(chunk_data),
+                _ => {}
+            },
+            ChunkDisposition::Text(text) => textual_data.push(text),
+            ChunkDisposition::CompressedText(text) => compressed_textual_data.push(text),
+            ChunkDisposition::InternationalText(text) => international_textual_data.push(text),
+        }
+    }
+
+    return Ok(BorrowedPngInfo {
+        info: state.collect()?,
+        palette,
+        image_data,
+        textual_data,
+        compressed_textual_data,
+        international_textual_data,
+        unknown_chunks,
+    });
+}
+
+/// Splits the chunk at the provided position off the buffer after checking its CRC, and advances the position past it.
+fn split_chunk<'a>(
+    data: &'a [u8],
+    position: &mut usize,
+) -> Result<(ChunkId, &'a [u8]), ParserError> {
+    let remaining = &data[*position..];
+    if remaining.len() < 8 {
+        return Err(ParserError::IOError(ErrorKind::UnexpectedEof.into()));
+    }
+
+    let length = utils::to_u32(&remaining[0..4]);
+    let chunk_type = utils::to_u32(&remaining[4..8]);
+
+    if length > MAX_CHUNK_LENGTH {
+        return Err(ParserError::InvalidChunkLength(chunk_type));
+    }
+
+    let end = 8 + length as usize;
+    if remaining.len() < end + 4 {
+        return Err(ParserError::IOError(ErrorKind::UnexpectedEof.into()));
+    }
+
+    if crc32::compute(&remaining[4..end]) != utils::to_u32(&remaining[end..end + 4]) {
+        return Err(ParserError::CorruptedData);
+    }
+
+    *position += end + 4;
+
+    return Ok((chunk_type, &remaining[8..end]));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::deflate::{self, CompressionLevel};
+    use crate::parser::Parser;
+    use crate::test_utils::chunk;
+    use crate::writer::Writer;
+
+    fn contains(buffer: &[u8], part: &[u8]) -> bool {
+        return buffer.as_ptr_range().contains(&part.as_ptr());
+    }
+
+    fn indexed_png() -> Vec<u8> {
+        let mut info = PngInfo::new(
+            HeaderInfo {
+                width: 2,
+                height: 1,
+                bit_depth: 8,
+                color_type: ColorType::IndexedColor,
+                compression_method: CompressionMethod::Deflate,
+                filter_method: FilterMethod::Adaptive,
+                interlace_method: InterlaceMethod::None,
+            },
+            CompressedDataInfo {
+                chunk_count: 2,
+                chunk_lengths: Vec::new(),
+                data: deflate::compress(&[0, 0, 1], CompressionLevel::Default),
+            },
+        );
+        info.palette = Some(PaletteInfo {
+            entries: vec![(255, 0, 0), (0, 0, 255)],
+        });
+        info.textual_data.push(TextualDataInfo {
+            keyword: String::from("Comment"),
+            text: String::from("café"),
+            position: ChunkPosition::BeforeImageData,
+        });
+        info.international_textual_data
+            .push(InternationalTextualDataInfo {
+                keyword: String::from("Title"),
+                is_compressed: false,
+                compression_method: CompressionMethod::Deflate,
+                language_tag: String::from("tr"),
+                translated_keyword: String::from("Başlık"),
+                text: "Örnek".as_bytes().to_vec(),
+                position: ChunkPosition::BeforeImageData,
+            });
+        info.unknown_chunks.push(UnknownChunkInfo {
+            chunk_type: *b"prVt",
+            data: vec![1, 2, 3],
+            position: ChunkPosition::BeforeImageData,
+        });
+
+        let mut png = Vec::new();
+        Writer::write(&mut png, &info).unwrap();
+        return png;
+    }
+
+    #[test]
+    fn test_parse_slice() {
+        let png = indexed_png();
+        let borrowed = parse_slice(&png).unwrap();
+
+        assert_eq!(borrowed.palette, Some(&[[255, 0, 0], [0, 0, 255]][..]));
+        assert!(contains(&png, borrowed.palette.unwrap().as_flattened()));
+        assert_eq!(borrowed.image_data.len(), 2);
+        assert!(borrowed.image_data.iter().all(|data| contains(&png, data)));
+        assert!(matches!(
+            borrowed.textual_data[0].keyword,
+            Cow::Borrowed("Comment")
+        ));
+        assert!(matches!(&borrowed.textual_data[0].text, Cow::Owned(text) if text == "café"));
+        assert!(contains(
+            &png,
+            borrowed.international_textual_data[0]
+                .translated_keyword
+                .as_bytes()
+        ));
+        assert_eq!(
+            borrowed.international_textual_data[0].translated_keyword,
+            "Başlık"
+        );
+        assert!(contains(&png, borrowed.unknown_chunks[0].data));
+        assert!(
+            borrowed.info.textual_data.is_empty() && borrowed.info.compressed_data.data.is_empty()
+        );
+
+        let owned = Parser::parse(&mut Cursor::new(&png)).unwrap();
+        assert_eq!(*borrowed.compressed_data(), owned.compressed_data.data);
+
+        // Copying the borrowed chunks back yields the same image as the owning parser.
+        let mut written = Vec::new();
+        Writer::write(&mut written, &borrowed.into_info()).unwrap();
+        assert_eq!(written, png);
+    }
+
+    #[test]
+    fn test_parse_slice_truncated() {
+        let png = indexed_png();
+        assert!(matches!(
+            parse_slice(&png[..png.len() - 12]),
+            Err(ParserError::MissingRequiredChunk(chunk_ids::IEND))
+        ));
+        assert!(matches!(
+            parse_slice(&png[..png.len() - 3]),
+            Err(ParserError::IOError(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_slice_signature_only() {
+        assert!(matches!(
+            parse_slice(&SIGNATURE),
+            Err(ParserError::MissingRequiredChunk(chunk_ids::IHDR))
+        ));
+        assert!(matches!(
+            parse_slice(&SIGNATURE[..4]),
+            Err(ParserError::IOError(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_slice_invalid_translated_keyword() {
+        // Unlike the Latin-1 keyword, the translated keyword is UTF-8, which a lone 0xff byte is not.
+        let mut png = indexed_png();
+        let iend = png.len() - 12;
+        png.splice(iend..iend, chunk(b"iTXt", b"Title\0\0\0tr\0\xffBa\0text"));
+
+        assert!(matches!(
+            parse_slice(&png),
+            Err(ParserError::InvalidTextEncoding)
+        ));
+        assert!(matches!(
+            Parser::parse(&mut Cursor::new(&png)),
+            Err(ParserError::InvalidTextEncoding)
+        ));
+    }
+}
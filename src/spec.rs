@@ -1,6 +1,18 @@
+use std::any::Any;
+use std::fmt;
+
+use crate::error::ParserError;
+use crate::exif::ExifMetadata;
+use crate::filter;
+use crate::inflate;
+use crate::utils;
+
 /// The byte values of PNG signature.
 pub const SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
 
+/// The default maximum number of bytes that a compressed chunk payload may decompress to.
+pub const DEFAULT_DECOMPRESSION_LIMIT: usize = 8 * 1024 * 1024;
+
 /// The type of each chunk id.
 pub type ChunkId = u32;
 
@@ -8,6 +20,8 @@ pub type ChunkId = u32;
 pub mod chunk_ids {
     use crate::spec::ChunkId;
 
+    /// Apple Optimized Image Marker
+    pub const CgBI: ChunkId = u32::from_be_bytes(*b"CgBI");
     /// Image Header
     pub const IHDR: ChunkId = u32::from_be_bytes(*b"IHDR");
     /// Palette
@@ -44,10 +58,38 @@ pub mod chunk_ids {
     pub const hIST: ChunkId = u32::from_be_bytes(*b"hIST");
     /// Image Last Modification Time
     pub const tIME: ChunkId = u32::from_be_bytes(*b"tIME");
+    /// Image Offset
+    pub const oFFs: ChunkId = u32::from_be_bytes(*b"oFFs");
+    /// Pixel Calibration
+    pub const pCAL: ChunkId = u32::from_be_bytes(*b"pCAL");
+    /// Physical Scale
+    pub const sCAL: ChunkId = u32::from_be_bytes(*b"sCAL");
+    /// Stereo Image Indicator
+    pub const sTER: ChunkId = u32::from_be_bytes(*b"sTER");
+    /// GIF Graphic Control Extension
+    pub const gIFg: ChunkId = u32::from_be_bytes(*b"gIFg");
+    /// GIF Application Extension
+    pub const gIFx: ChunkId = u32::from_be_bytes(*b"gIFx");
+    /// GIF Plain Text Extension
+    pub const gIFt: ChunkId = u32::from_be_bytes(*b"gIFt");
+    /// Coding-independent Code Points
+    pub const cICP: ChunkId = u32::from_be_bytes(*b"cICP");
+    /// Mastering Display Color Volume
+    pub const mDCv: ChunkId = u32::from_be_bytes(*b"mDCv");
+    /// Content Light Level Information
+    pub const cLLi: ChunkId = u32::from_be_bytes(*b"cLLi");
+    /// Exchangeable Image File Profile
+    pub const eXIf: ChunkId = u32::from_be_bytes(*b"eXIf");
+    /// Animation Control
+    pub const acTL: ChunkId = u32::from_be_bytes(*b"acTL");
+    /// Frame Control
+    pub const fcTL: ChunkId = u32::from_be_bytes(*b"fcTL");
+    /// Frame Data
+    pub const fdAT: ChunkId = u32::from_be_bytes(*b"fdAT");
 }
 
 /// Describes the pixel interpretation of an image data.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ColorType {
     /// Each pixel is a grayscale sample.
     Grayscale,
@@ -61,22 +103,60 @@ pub enum ColorType {
     TrueColorAlpha,
 }
 
+impl ColorType {
+    /// Returns the number of samples in each pixel.
+    pub fn channels(&self) -> u8 {
+        return match self {
+            ColorType::Grayscale | ColorType::IndexedColor => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::TrueColor => 3,
+            ColorType::TrueColorAlpha => 4,
+        };
+    }
+
+    /// Checks whether the bit depth is allowed for the color type.
+    pub fn allows_bit_depth(&self, bit_depth: u8) -> bool {
+        return match self {
+            ColorType::Grayscale => matches!(bit_depth, 1 | 2 | 4 | 8 | 16),
+            ColorType::IndexedColor => matches!(bit_depth, 1 | 2 | 4 | 8),
+            ColorType::TrueColor | ColorType::GrayscaleAlpha | ColorType::TrueColorAlpha => {
+                matches!(bit_depth, 8 | 16)
+            }
+        };
+    }
+}
+
 /// Describes the compression method used to compress data.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressionMethod {
     /// Deflate/Inflate compression with a sliding window of at most 32768 (2^15) bytes.
     Deflate,
 }
 
 /// Describes the preprocessing method applied to the image data before compression.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FilterMethod {
     /// Adaptive filtering with five basic filter types.
     Adaptive,
 }
 
+/// Describes the filter type applied to a scanline by adaptive filtering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterType {
+    /// The scanline is transmitted unmodified.
+    None,
+    /// Each byte is predicted from the corresponding byte of the pixel to the left.
+    Sub,
+    /// Each byte is predicted from the corresponding byte of the pixel above.
+    Up,
+    /// Each byte is predicted from the average of the pixels to the left and above.
+    Average,
+    /// Each byte is predicted by the Paeth predictor of the pixels to the left, above and upper left.
+    Paeth,
+}
+
 /// Describes the transmission order of the image data.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InterlaceMethod {
     /// No interlace,
     None,
@@ -85,7 +165,7 @@ pub enum InterlaceMethod {
 }
 
 /// Represents the info of `IHDR` chunk.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HeaderInfo {
     /// The width in pixels.
     pub width: u32,
@@ -103,6 +183,38 @@ pub struct HeaderInfo {
     pub interlace_method: InterlaceMethod,
 }
 
+impl HeaderInfo {
+    /// Checks whether the bit depth is allowed for the color type.
+    pub fn has_valid_bit_depth(&self) -> bool {
+        return self.color_type.allows_bit_depth(self.bit_depth);
+    }
+
+    /// Returns the number of bits in each pixel.
+    pub fn bits_per_pixel(&self) -> usize {
+        return self.color_type.channels() as usize * self.bit_depth as usize;
+    }
+
+    /// Returns the number of bytes in each pixel rounded up to one, as used by the filters.
+    pub fn bytes_per_pixel(&self) -> usize {
+        return self.bits_per_pixel().div_ceil(8);
+    }
+
+    /// Returns the number of bytes in each unfiltered scanline.
+    pub fn stride(&self) -> usize {
+        return filter::stride(self.width, self.bits_per_pixel());
+    }
+}
+
+/// Represents the info of `CgBI` chunk, which marks images optimized by Apple's tools.
+///
+/// The image data of such images is a raw deflate stream without the zlib header and checksum, and the samples
+/// of true-color images are stored in the BGR(A) order with the alpha premultiplied into the colors.
+#[derive(Debug)]
+pub struct CgBIInfo {
+    /// The undocumented flags.
+    pub flags: u32,
+}
+
 /// Represents the info of `PLTE` chunk.
 #[derive(Debug)]
 pub struct PaletteInfo {
@@ -115,10 +227,36 @@ pub struct PaletteInfo {
 pub struct CompressedDataInfo {
     /// The number of chunks.
     pub chunk_count: u32,
+    /// The length of each chunk in the original stream.
+    ///
+    /// The writer reuses the lengths when they add up to the length of the data, and otherwise splits the data
+    /// into `chunk_count` chunks whose lengths differ by at most one byte.
+    pub chunk_lengths: Vec<u32>,
     /// The compressed pixel data.
     pub data: Vec<u8>,
 }
 
+/// Describes where an ancillary chunk without a fixed position was placed relative to the critical chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChunkPosition {
+    /// Before the `PLTE` chunk, or before the image data if there is no palette.
+    BeforePalette,
+    /// After the `PLTE` chunk and before the image data.
+    #[default]
+    BeforeImageData,
+    /// After the image data.
+    AfterImageData,
+}
+
+/// Represents the position of the data of an `IDAT` chunk that was left in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataSpan {
+    /// The position of the chunk data from the start of the PNG data stream, which is the start of its signature.
+    pub offset: u64,
+    /// The length of the chunk data.
+    pub length: u32,
+}
+
 /// Represents the info of `IEND` chunk.
 #[derive(Debug)]
 pub struct TrailerInfo {
@@ -127,7 +265,7 @@ pub struct TrailerInfo {
 }
 
 /// Describes the transparency.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum TransparencyVariant {
     /// For grayscale images, a single gray level value.
     Grayscale(u16),
@@ -195,6 +333,18 @@ pub struct ICCProfileInfo {
     pub compressed_profile_data: Vec<u8>,
 }
 
+impl ICCProfileInfo {
+    /// Decompresses the profile data with the default size limit.
+    pub fn decompress_profile(&self) -> Result<Vec<u8>, ParserError> {
+        return self.decompress_profile_with_limit(DEFAULT_DECOMPRESSION_LIMIT);
+    }
+
+    /// Decompresses the profile data, failing if it exceeds the provided number of bytes.
+    pub fn decompress_profile_with_limit(&self, limit: usize) -> Result<Vec<u8>, ParserError> {
+        return inflate::decompress_with_limit(&self.compressed_profile_data, limit);
+    }
+}
+
 /// Represents the info of `tEXt` chunk.
 #[derive(Debug)]
 pub struct TextualDataInfo {
@@ -202,6 +352,8 @@ pub struct TextualDataInfo {
     pub keyword: String,
     /// The text.
     pub text: String,
+    /// The position of the chunk in the stream.
+    pub position: ChunkPosition,
 }
 
 /// Represents the info of `zTXt` chunk.
@@ -213,6 +365,21 @@ pub struct CompressedTextualDataInfo {
     pub compression_method: CompressionMethod,
     /// The compressed text data.
     pub text: Vec<u8>,
+    /// The position of the chunk in the stream.
+    pub position: ChunkPosition,
+}
+
+impl CompressedTextualDataInfo {
+    /// Decompresses the text with the default size limit and decodes it as Latin-1.
+    pub fn decompress_text(&self) -> Result<String, ParserError> {
+        return self.decompress_text_with_limit(DEFAULT_DECOMPRESSION_LIMIT);
+    }
+
+    /// Decompresses the text, failing if it exceeds the provided number of bytes, and decodes it as Latin-1.
+    pub fn decompress_text_with_limit(&self, limit: usize) -> Result<String, ParserError> {
+        let text = inflate::decompress_with_limit(&self.text, limit)?;
+        return Ok(utils::latin1_to_string(&text));
+    }
 }
 
 /// Represents the info of `iTXt` chunk.
@@ -230,6 +397,28 @@ pub struct InternationalTextualDataInfo {
     pub translated_keyword: String,
     /// The international (maybe compressed) text data.
     pub text: Vec<u8>,
+    /// The position of the chunk in the stream.
+    pub position: ChunkPosition,
+}
+
+impl InternationalTextualDataInfo {
+    /// Decompresses the text if necessary with the default size limit and decodes it as UTF-8.
+    pub fn decode_text(&self) -> Result<String, ParserError> {
+        return self.decode_text_with_limit(DEFAULT_DECOMPRESSION_LIMIT);
+    }
+
+    /// Decompresses the text if necessary, failing if it exceeds the provided number of bytes, and decodes it as UTF-8.
+    pub fn decode_text_with_limit(&self, limit: usize) -> Result<String, ParserError> {
+        let text = match self.is_compressed {
+            true => inflate::decompress_with_limit(&self.text, limit)?,
+            false => self.text.clone(),
+        };
+
+        return match String::from_utf8(text) {
+            Ok(text) => Ok(text),
+            Err(_) => Err(ParserError::InvalidTextEncoding),
+        };
+    }
 }
 
 /// Describes the default background color of image.
@@ -340,6 +529,319 @@ pub struct LastModificationInfo {
     pub second: u8,
 }
 
+/// Describes the unit of image offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetUnit {
+    /// The offset is in pixels.
+    Pixel,
+    /// The offset is in micrometers.
+    Micrometer,
+}
+
+/// Represents the info of `oFFs` chunk.
+#[derive(Debug)]
+pub struct ImageOffsetInfo {
+    /// The position of the image on X and Y axes.
+    pub position: (i32, i32),
+    /// The unit of the position.
+    pub unit: OffsetUnit,
+}
+
+/// Describes the equation that maps the original samples to the physical values.
+///
+/// In the equations, `x` is the original sample value and `r` is the original range, `original_max - original_zero`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquationType {
+    /// Linear mapping, `p0 + p1 * x / r` with two parameters.
+    Linear,
+    /// Exponential mapping, `p0 + p1 * e^(p2 * x / r)` with three parameters.
+    Exponential,
+    /// Exponential mapping with arbitrary base, `p0 + p1 * p3^(p2 * x / r)` with four parameters.
+    ArbitraryExponential,
+    /// Hyperbolic mapping, `p0 + p1 * sinh(p2 * (x - p3) / r)` with four parameters.
+    Hyperbolic,
+}
+
+impl EquationType {
+    /// Returns the number of parameters that the equation requires.
+    pub fn parameter_count(&self) -> usize {
+        return match self {
+            EquationType::Linear => 2,
+            EquationType::Exponential => 3,
+            EquationType::ArbitraryExponential | EquationType::Hyperbolic => 4,
+        };
+    }
+}
+
+/// Represents the info of `pCAL` chunk.
+#[derive(Debug)]
+pub struct PixelCalibrationInfo {
+    /// The name of the calibration.
+    pub name: String,
+    /// The original sample value that the zero sample maps to.
+    pub original_zero: i32,
+    /// The original sample value that the maximum sample maps to.
+    pub original_max: i32,
+    /// The equation type.
+    pub equation_type: EquationType,
+    /// The unit of the physical values.
+    pub unit_name: String,
+    /// The parameters of the equation.
+    pub parameters: Vec<f64>,
+}
+
+impl PixelCalibrationInfo {
+    /// Computes the physical value of the provided sample.
+    ///
+    /// # Arguments
+    /// * `sample` - The stored sample value.
+    /// * `max_sample` - The maximum sample value of the bit depth, such as 255 for 8-bit samples.
+    ///
+    /// # Returns
+    /// * `f64` - The physical value in the unit of the calibration.
+    pub fn physical_value(&self, sample: u32, max_sample: u32) -> f64 {
+        let range = self.original_max as i64 - self.original_zero as i64;
+        let max_sample = max_sample.max(1) as i64;
+
+        // The original value is computed with integer arithmetic, rounding half away from zero.
+        let scaled = sample as i64 * range;
+        let original = match scaled >= 0 {
+            true => (scaled + max_sample / 2) / max_sample,
+            false => (scaled - max_sample / 2) / max_sample,
+        } + self.original_zero as i64;
+
+        let (original, range) = (original as f64, range as f64);
+        let p = &self.parameters;
+
+        return match self.equation_type {
+            EquationType::Linear => p[0] + p[1] * original / range,
+            EquationType::Exponential => p[0] + p[1] * (p[2] * original / range).exp(),
+            EquationType::ArbitraryExponential => p[0] + p[1] * p[3].powf(p[2] * original / range),
+            EquationType::Hyperbolic => p[0] + p[1] * (p[2] * (original - p[3]) / range).sinh(),
+        };
+    }
+}
+
+/// Describes the unit of physical scale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleUnit {
+    /// The scale is in meters per pixel.
+    Meter,
+    /// The scale is in radians per pixel.
+    Radian,
+}
+
+/// Represents the info of `sCAL` chunk.
+#[derive(Debug)]
+pub struct PhysicalScaleInfo {
+    /// The unit of the scale.
+    pub unit: ScaleUnit,
+    /// The width of a pixel.
+    pub pixel_width: f64,
+    /// The height of a pixel.
+    pub pixel_height: f64,
+}
+
+/// Describes the layout of the stereo sub-images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoLayout {
+    /// The right-eye image is on the left, for cross-fused viewing.
+    CrossFuse,
+    /// The left-eye image is on the left, for divergent viewing.
+    DivergingFuse,
+}
+
+/// Represents the info of `sTER` chunk.
+#[derive(Debug)]
+pub struct StereoImageInfo {
+    /// The layout of the sub-images.
+    pub layout: StereoLayout,
+}
+
+/// Represents the info of `gIFg` chunk.
+#[derive(Debug)]
+pub struct GifGraphicControlInfo {
+    /// The GIF disposal method.
+    pub disposal_method: u8,
+    /// Whether user input is expected before continuing.
+    pub user_input: bool,
+    /// The delay time in hundredths of a second.
+    pub delay_time: u16,
+}
+
+/// Represents the info of `gIFx` chunk.
+#[derive(Debug)]
+pub struct GifApplicationExtensionInfo {
+    /// The application identifier.
+    pub application_identifier: [u8; 8],
+    /// The authentication code.
+    pub authentication_code: [u8; 3],
+    /// The application data.
+    pub data: Vec<u8>,
+}
+
+/// Represents the info of `gIFt` chunk.
+#[derive(Debug)]
+pub struct GifPlainTextExtensionInfo {
+    /// The position of the text grid on X and Y axes.
+    pub grid_position: (i32, i32),
+    /// The width and height of the text grid.
+    pub grid_size: (u32, u32),
+    /// The width and height of each character cell.
+    pub cell_size: (u8, u8),
+    /// The RGB foreground color.
+    pub foreground: (u8, u8, u8),
+    /// The RGB background color.
+    pub background: (u8, u8, u8),
+    /// The text.
+    pub text: String,
+}
+
+/// Represents the info of `cICP` chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodingIndependentCodePointsInfo {
+    /// The color primaries as defined in ITU-T H.273.
+    pub color_primaries: u8,
+    /// The transfer function as defined in ITU-T H.273.
+    pub transfer_function: u8,
+    /// The matrix coefficients as defined in ITU-T H.273, always 0 since PNG only stores RGB.
+    pub matrix_coefficients: u8,
+    /// Whether the samples use the full range instead of the narrow range.
+    pub is_full_range: bool,
+}
+
+impl CodingIndependentCodePointsInfo {
+    /// Returns whether the transfer function is the perceptual quantizer (SMPTE ST 2084).
+    pub fn is_perceptual_quantizer(&self) -> bool {
+        return self.transfer_function == 16;
+    }
+
+    /// Returns whether the transfer function is hybrid log-gamma (ARIB STD-B67).
+    pub fn is_hybrid_log_gamma(&self) -> bool {
+        return self.transfer_function == 18;
+    }
+}
+
+/// Represents the info of `mDCv` chunk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MasteringDisplayColorVolumeInfo {
+    /// The red chromaticity of X and Y axes.
+    pub red: (f32, f32),
+    /// The green chromaticity of X and Y axes.
+    pub green: (f32, f32),
+    /// The blue chromaticity of X and Y axes.
+    pub blue: (f32, f32),
+    /// The white point chromaticity of X and Y axes.
+    pub white_point: (f32, f32),
+    /// The maximum luminance in candelas per square meter, or 0 if unknown.
+    pub max_luminance: f32,
+    /// The minimum luminance in candelas per square meter, or 0 if unknown.
+    pub min_luminance: f32,
+}
+
+/// Represents the info of `cLLi` chunk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContentLightLevelInfo {
+    /// The maximum content light level in candelas per square meter, or 0 if unknown.
+    pub max_content_light_level: f32,
+    /// The maximum frame-average light level in candelas per square meter, or 0 if unknown.
+    pub max_frame_average_light_level: f32,
+}
+
+/// Represents the info of `eXIf` chunk.
+#[derive(Debug)]
+pub struct ExifInfo {
+    /// The raw profile starting with the TIFF header.
+    pub data: Vec<u8>,
+    /// The metadata read from the profile, or `None` if its header or primary directory is invalid.
+    pub metadata: Option<ExifMetadata>,
+}
+
+/// Represents the info of `acTL` chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationControlInfo {
+    /// The number of frames in the animation.
+    pub frame_count: u32,
+    /// The number of times to loop the animation, or 0 to loop infinitely.
+    pub play_count: u32,
+}
+
+/// Describes how the frame area is disposed before rendering the next frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisposeOp {
+    /// The frame area is left as it is.
+    None,
+    /// The frame area is cleared to fully transparent black.
+    Background,
+    /// The frame area is reverted to its contents before rendering the frame.
+    Previous,
+}
+
+/// Describes how the frame is rendered onto the frame area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendOp {
+    /// The frame replaces the frame area, including its alpha.
+    Source,
+    /// The frame is composited over the frame area using its alpha.
+    Over,
+}
+
+/// Represents the info of `fcTL` chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameControlInfo {
+    /// The sequence number of the chunk in the animation.
+    pub sequence_number: u32,
+    /// The width of the frame.
+    pub width: u32,
+    /// The height of the frame.
+    pub height: u32,
+    /// The horizontal position of the frame on the canvas.
+    pub x_offset: u32,
+    /// The vertical position of the frame on the canvas.
+    pub y_offset: u32,
+    /// The numerator of the frame delay in seconds.
+    pub delay_numerator: u16,
+    /// The denominator of the frame delay in seconds, where 0 is treated as 100.
+    pub delay_denominator: u16,
+    /// The disposal applied after rendering the frame.
+    pub dispose_op: DisposeOp,
+    /// The blending applied when rendering the frame.
+    pub blend_op: BlendOp,
+}
+
+impl FrameControlInfo {
+    /// Returns the frame delay in seconds.
+    pub fn delay(&self) -> f64 {
+        let denominator = match self.delay_denominator {
+            0 => 100,
+            denominator => denominator,
+        };
+
+        return self.delay_numerator as f64 / denominator as f64;
+    }
+}
+
+/// Represents the info of consecutive `fdAT` chunks of a frame.
+#[derive(Debug)]
+pub struct FrameDataInfo {
+    /// The number of `fdAT` chunks.
+    pub chunk_count: u32,
+    /// The length of the frame data in each chunk in the original stream, which is reused by the writer like
+    /// `CompressedDataInfo::chunk_lengths`.
+    pub chunk_lengths: Vec<u32>,
+    /// The compressed frame data without the sequence numbers.
+    pub data: Vec<u8>,
+}
+
+/// Represents the info of an animation frame.
+#[derive(Debug)]
+pub struct FrameInfo {
+    /// The frame control.
+    pub control: FrameControlInfo,
+    /// The frame data, or `None` if the frame is the default image stored in `IDAT` chunks.
+    pub data: Option<FrameDataInfo>,
+}
+
 /// Represents the info of an unknown chunk.
 #[derive(Debug)]
 pub struct UnknownChunkInfo {
@@ -347,17 +849,49 @@ pub struct UnknownChunkInfo {
     pub chunk_type: [u8; 4],
     /// The raw data.
     pub data: Vec<u8>,
+    /// The position of the chunk in the stream.
+    pub position: ChunkPosition,
+}
+
+/// Represents the info of a chunk that was turned into a typed value by a registered chunk handler.
+pub struct CustomChunkInfo {
+    /// The chunk type.
+    pub chunk_type: [u8; 4],
+    /// The raw data, kept so that the chunk can be written back.
+    pub data: Vec<u8>,
+    /// The position of the chunk in the stream.
+    pub position: ChunkPosition,
+    /// The number of unknown chunks before the chunk, which keeps the order of the two kinds when writing.
+    pub unknown_chunk_index: usize,
+    /// The value produced by the handler.
+    pub value: Box<dyn Any + Send + Sync>,
+}
+
+impl fmt::Debug for CustomChunkInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f
+            .debug_struct("CustomChunkInfo")
+            .field("chunk_type", &self.chunk_type)
+            .field("data", &self.data)
+            .field("position", &self.position)
+            .field("unknown_chunk_index", &self.unknown_chunk_index)
+            .finish_non_exhaustive();
+    }
 }
 
 /// Represents the info of a PNG image.
 #[derive(Debug)]
 pub struct PngInfo {
+    /// The Apple optimized image marker.
+    pub cgbi: Option<CgBIInfo>,
     /// The header.
     pub header: HeaderInfo,
     /// The palette.
     pub palette: Option<PaletteInfo>,
     /// The compressed data.
     pub compressed_data: CompressedDataInfo,
+    /// The positions of the `IDAT` chunk data, only recorded when the data is left in the source.
+    pub image_data_spans: Vec<DataSpan>,
     /// The trailer.
     pub trailer: TrailerInfo,
     /// The transparency values.
@@ -388,6 +922,139 @@ pub struct PngInfo {
     pub palette_histogram: Option<PaletteHistogramInfo>,
     /// The last modification time.
     pub last_modification: Option<LastModificationInfo>,
+    /// The image offset.
+    pub image_offset: Option<ImageOffsetInfo>,
+    /// The pixel calibration.
+    pub pixel_calibration: Option<PixelCalibrationInfo>,
+    /// The physical scale.
+    pub physical_scale: Option<PhysicalScaleInfo>,
+    /// The stereo image indicator.
+    pub stereo_image: Option<StereoImageInfo>,
+    /// The vector of GIF graphic control extensions.
+    pub gif_graphic_controls: Vec<GifGraphicControlInfo>,
+    /// The vector of GIF application extensions.
+    pub gif_application_extensions: Vec<GifApplicationExtensionInfo>,
+    /// The vector of GIF plain text extensions.
+    pub gif_plain_text_extensions: Vec<GifPlainTextExtensionInfo>,
+    /// The coding-independent code points.
+    pub coding_independent_code_points: Option<CodingIndependentCodePointsInfo>,
+    /// The mastering display color volume.
+    pub mastering_display_color_volume: Option<MasteringDisplayColorVolumeInfo>,
+    /// The content light level.
+    pub content_light_level: Option<ContentLightLevelInfo>,
+    /// The exchangeable image file profile.
+    pub exif: Option<ExifInfo>,
     /// The unidentified chunks.
     pub unknown_chunks: Vec<UnknownChunkInfo>,
+    /// The chunks handled by registered chunk handlers.
+    pub custom_chunks: Vec<CustomChunkInfo>,
+    /// The animation control.
+    pub animation_control: Option<AnimationControlInfo>,
+    /// The vector of animation frames.
+    pub animation_frames: Vec<FrameInfo>,
+    /// The type of every chunk in the order it was read, which the writer follows while it still lists exactly the
+    /// chunks of the info. It is empty for info that was not read from a stream.
+    pub chunk_order: Vec<[u8; 4]>,
+}
+
+impl PngInfo {
+    /// Creates the info of an image with the provided header and compressed data, and no ancillary chunks.
+    pub fn new(header: HeaderInfo, compressed_data: CompressedDataInfo) -> Self {
+        return Self {
+            cgbi: None,
+            header,
+            palette: None,
+            compressed_data,
+            image_data_spans: Vec::new(),
+            trailer: TrailerInfo { found: true },
+            transparency: None,
+            gamma: None,
+            chromaticity: None,
+            standard_rgb: None,
+            icc_profile: None,
+            textual_data: Vec::new(),
+            compressed_textual_data: Vec::new(),
+            international_textual_data: Vec::new(),
+            background: None,
+            physical_pixel_dimension: None,
+            significant_bits: None,
+            suggested_palettes: Vec::new(),
+            palette_histogram: None,
+            last_modification: None,
+            image_offset: None,
+            pixel_calibration: None,
+            physical_scale: None,
+            stereo_image: None,
+            gif_graphic_controls: Vec::new(),
+            gif_application_extensions: Vec::new(),
+            gif_plain_text_extensions: Vec::new(),
+            coding_independent_code_points: None,
+            mastering_display_color_volume: None,
+            content_light_level: None,
+            exif: None,
+            unknown_chunks: Vec::new(),
+            custom_chunks: Vec::new(),
+            animation_control: None,
+            animation_frames: Vec::new(),
+            chunk_order: Vec::new(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deflate::{self, CompressionLevel};
+
+    #[test]
+    fn test_decompress_text_latin1() {
+        let info = CompressedTextualDataInfo {
+            keyword: String::from("Comment"),
+            compression_method: CompressionMethod::Deflate,
+            text: deflate::compress(&[b'c', b'a', b'f', 0xe9], CompressionLevel::Default),
+            position: ChunkPosition::BeforeImageData,
+        };
+        assert_eq!(info.decompress_text().unwrap(), "café");
+        assert!(matches!(
+            info.decompress_text_with_limit(3),
+            Err(ParserError::DecompressedSizeLimitExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_decode_international_text() {
+        let mut info = InternationalTextualDataInfo {
+            keyword: String::from("Title"),
+            is_compressed: true,
+            compression_method: CompressionMethod::Deflate,
+            language_tag: String::from("ja"),
+            translated_keyword: String::new(),
+            text: deflate::compress("日本".as_bytes(), CompressionLevel::Default),
+            position: ChunkPosition::BeforeImageData,
+        };
+        assert_eq!(info.decode_text().unwrap(), "日本");
+
+        info.is_compressed = false;
+        info.text = vec![0xff, 0xfe];
+        assert!(matches!(
+            info.decode_text(),
+            Err(ParserError::InvalidTextEncoding)
+        ));
+    }
+
+    #[test]
+    fn test_hyperbolic_physical_value() {
+        let info = PixelCalibrationInfo {
+            name: String::from("Temperature"),
+            original_zero: 10,
+            original_max: 210,
+            equation_type: EquationType::Hyperbolic,
+            unit_name: String::from("K"),
+            parameters: vec![1.0, 2.0, 4.0, 50.0],
+        };
+
+        // The samples map to the original values 10 and 210, which are offset by p3 before being divided by 200.
+        assert_eq!(info.physical_value(0, 255), 1.0 + 2.0 * (-0.8f64).sinh());
+        assert_eq!(info.physical_value(255, 255), 1.0 + 2.0 * 3.2f64.sinh());
+    }
 }
@@ -0,0 +1,643 @@
+use std::collections::VecDeque;
+
+use crate::chunk::MAX_CHUNK_LENGTH;
+use crate::convert;
+use crate::crc32;
+use crate::error::ParserError;
+use crate::filter;
+use crate::image::ImageLayout;
+use crate::inflate::StreamInflater;
+use crate::interlace::{self, ADAM7_PASSES};
+use crate::parser::{ImageDataHandling, ParserOptions, ParserState};
+use crate::spec::*;
+use crate::utils;
+
+/// Describes a step of decoding reported by `StreamDecoder::next_event`.
+#[derive(Debug)]
+pub enum DecodeEvent {
+    /// The data fed so far has been processed, and decoding continues once more data is fed.
+    NeedMoreData,
+    /// The header has been read, so the layout of the image is known.
+    Header(HeaderInfo),
+    /// A chunk has been read and validated, including its CRC.
+    Chunk {
+        /// The chunk type.
+        chunk_type: [u8; 4],
+        /// The length of the chunk data.
+        length: u32,
+    },
+    /// A scanline has been reconstructed and copied into the pixels of the decoder.
+    Row {
+        /// The Adam7 pass the scanline belongs to, or `None` for non-interlaced images.
+        pass: Option<u8>,
+        /// The row of the image the scanline is placed in.
+        y: u32,
+        /// The unfiltered scanline, which only holds the pixels of the pass for interlaced images.
+        data: Vec<u8>,
+    },
+    /// Every scanline of the provided Adam7 pass has been reconstructed, which is reported for empty passes too.
+    PassComplete(u8),
+    /// The trailer has been read, and the info of the image is complete.
+    End(Box<PngInfo>),
+}
+
+/// Describes the part of the stream that the decoder expects next.
+enum Stage {
+    Signature,
+    ChunkHeader,
+    ChunkData {
+        chunk_type: ChunkId,
+        length: u32,
+    },
+    ImageData {
+        length: u32,
+        remaining: u32,
+        crc: u32,
+    },
+    Finished,
+}
+
+/// Represents a decoder that is pushed PNG data in fragments of any size and reports progress as events.
+///
+/// The image data is decompressed while it arrives, so scanlines are reported as soon as they are complete, which
+/// allows rendering an image row by row, or Adam7 pass by pass. Running out of data is never an error; the decoder
+/// reports `DecodeEvent::NeedMoreData` instead and continues once more data is fed. An error leaves the decoder in an
+/// unspecified state, so it should be discarded afterwards.
+pub struct StreamDecoder<'a> {
+    state: ParserState<'a>,
+    stage: Stage,
+    buffer: Vec<u8>,
+    position: usize,
+    offset: u64,
+    /// Whether the image data is decompressed into scanlines, rather than only validated as chunks.
+    decodes_rows: bool,
+    inflater: Option<StreamInflater>,
+    rows: Option<RowDecoder>,
+    events: VecDeque<DecodeEvent>,
+}
+
+impl<'a> StreamDecoder<'a> {
+    /// Creates a decoder with the default options.
+    pub fn new() -> Self {
+        return Self::from_state(ParserState::new(None));
+    }
+
+    /// Creates a decoder with the provided options.
+    ///
+    /// The data of `IDAT` chunks is kept in the info reported at the end only with `ImageDataHandling::Buffer`,
+    /// and `ImageDataHandling::Skip` also skips checking its CRC.
+    pub fn with_options(options: &'a ParserOptions) -> Self {
+        return Self::from_state(ParserState::new(Some(options)));
+    }
+
+    /// Creates a decoder that reads the chunks as `Parser` does, without decompressing the image data.
+    #[cfg(feature = "async")]
+    pub(crate) fn without_rows(options: Option<&'a ParserOptions>) -> Self {
+        return Self {
+            decodes_rows: false,
+            ..Self::from_state(ParserState::new(options))
+        };
+    }
+
+    fn from_state(state: ParserState<'a>) -> Self {
+        return Self {
+            state,
+            stage: Stage::Signature,
+            buffer: Vec::new(),
+            position: 0,
+            offset: 0,
+            decodes_rows: true,
+            inflater: None,
+            rows: None,
+            events: VecDeque::new(),
+        };
+    }
+
+    /// Appends the provided data to the data awaiting decoding. Data fed after the trailer is ignored.
+    pub fn feed(&mut self, data: &[u8]) {
+        if let Stage::Finished = self.stage {
+            return;
+        }
+
+        // The processed data is never read again, so it is dropped before the buffer grows.
+        self.buffer.drain(..self.position);
+        self.offset += self.position as u64;
+        self.position = 0;
+
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Decodes the data fed so far until the next event.
+    ///
+    /// # Returns
+    /// * `Result<DecodeEvent, ParserError>` - The next event, `DecodeEvent::NeedMoreData` if the data fed so far has
+    ///   been processed, or the error that invalidates the stream.
+    pub fn next_event(&mut self) -> Result<DecodeEvent, ParserError> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(event);
+            }
+
+            if !self.advance()? {
+                return Ok(DecodeEvent::NeedMoreData);
+            }
+        }
+    }
+
+    /// Returns whether the trailer has been read.
+    pub fn is_finished(&self) -> bool {
+        return matches!(self.stage, Stage::Finished) && self.events.is_empty();
+    }
+
+    /// Ends a stream whose data has all been fed without reaching the trailer.
+    ///
+    /// # Returns
+    /// * `Result<PngInfo, ParserError>` - The error for the missing trailer if the data ended at a chunk boundary,
+    ///   or an unexpected end of file error otherwise.
+    #[cfg(feature = "async")]
+    pub(crate) fn finish(mut self) -> Result<PngInfo, ParserError> {
+        if !matches!(self.stage, Stage::ChunkHeader) || self.position < self.buffer.len() {
+            return Err(ParserError::IOError(
+                std::io::ErrorKind::UnexpectedEof.into(),
+            ));
+        }
+
+        self.finish_image_data()?;
+        return self.state.collect();
+    }
+
+    /// Returns the scanlines reconstructed so far, in the same layout as `Image::pixels`.
+    ///
+    /// Pixels that have not been decoded yet are zero, and the buffer is empty until the first scanline is decoded.
+    pub fn pixels(&self) -> &[u8] {
+        return match &self.rows {
+            Some(rows) => &rows.pixels,
+            None => &[],
+        };
+    }
+
+    /// Processes the next part of the stream, and returns whether enough data was available to do so.
+    fn advance(&mut self) -> Result<bool, ParserError> {
+        let available = &self.buffer[self.position..];
+
+        match self.stage {
+            Stage::Signature => {
+                if available.len() < SIGNATURE.len() {
+                    return Ok(false);
+                }
+
+                if available[..SIGNATURE.len()] != SIGNATURE {
+                    return Err(ParserError::InvalidSignature);
+                }
+
+                self.position += SIGNATURE.len();
+                self.stage = Stage::ChunkHeader;
+            }
+            Stage::ChunkHeader => {
+                if available.len() < 8 {
+                    return Ok(false);
+                }
+
+                let length = utils::to_u32(&available[..4]);
+                let chunk_type = utils::to_u32(&available[4..8]);
+                let crc = crc32::compute(&available[4..8]);
+
+                if length > MAX_CHUNK_LENGTH {
+                    return Err(ParserError::InvalidChunkLength(chunk_type));
+                }
+
+                if chunk_type == chunk_ids::IDAT {
+                    self.state.process_image_data_span(DataSpan {
+                        offset: self.offset + self.position as u64 + 8,
+                        length,
+                    })?;
+                    self.start_image_data()?;
+                    self.stage = Stage::ImageData {
+                        length,
+                        remaining: length,
+                        crc,
+                    };
+                } else {
+                    self.finish_image_data()?;
+                    self.stage = Stage::ChunkData { chunk_type, length };
+                }
+
+                self.position += 8;
+            }
+            Stage::ChunkData { chunk_type, length } => {
+                let length = length as usize;
+                if available.len() < length + 4 {
+                    return Ok(false);
+                }
+
+                let data = &available[..length];
+                let crc = crc32::update(crc32::compute(&chunk_type.to_be_bytes()), data);
+                if utils::to_u32(&available[length..length + 4]) != crc {
+                    return Err(ParserError::CorruptedData);
+                }
+
+                self.state.process_chunk(chunk_type, data)?;
+                self.position += length + 4;
+
+                self.events.push_back(DecodeEvent::Chunk {
+                    chunk_type: chunk_type.to_be_bytes(),
+                    length: length as u32,
+                });
+
+                if chunk_type == chunk_ids::IHDR
+                    && let Some(header) = self.state.header()
+                {
+                    self.events.push_back(DecodeEvent::Header(header.clone()));
+                }
+
+                if self.state.is_finished() {
+                    let state = ParserState::new(self.state.options());
+                    let state = std::mem::replace(&mut self.state, state);
+                    self.events
+                        .push_back(DecodeEvent::End(Box::new(state.collect()?)));
+                    self.stage = Stage::Finished;
+                } else {
+                    self.stage = Stage::ChunkHeader;
+                }
+            }
+            Stage::ImageData {
+                length,
+                remaining: 0,
+                crc,
+            } => {
+                if available.len() < 4 {
+                    return Ok(false);
+                }
+
+                let handling = self.state.image_data_handling();
+                if handling != ImageDataHandling::Skip && utils::to_u32(&available[..4]) != crc {
+                    return Err(ParserError::CorruptedData);
+                }
+
+                self.position += 4;
+                self.events.push_back(DecodeEvent::Chunk {
+                    chunk_type: chunk_ids::IDAT.to_be_bytes(),
+                    length,
+                });
+                self.stage = Stage::ChunkHeader;
+            }
+            Stage::ImageData {
+                length,
+                remaining,
+                crc,
+            } => {
+                if available.is_empty() {
+                    return Ok(false);
+                }
+
+                let count = available.len().min(remaining as usize);
+                let data = &available[..count];
+
+                if self.state.image_data_handling() == ImageDataHandling::Buffer {
+                    self.state.append_image_data(data);
+                }
+
+                if let (Some(inflater), Some(rows)) = (&mut self.inflater, &mut self.rows) {
+                    inflater.write(data, false)?;
+                    inflater.consume(rows.decode(inflater.output(), &mut self.events)?);
+                }
+
+                self.position += count;
+                self.stage = Stage::ImageData {
+                    length,
+                    remaining: remaining - count as u32,
+                    crc: match self.state.image_data_handling() {
+                        ImageDataHandling::Skip => crc,
+                        _ => crc32::update(crc, data),
+                    },
+                };
+            }
+            Stage::Finished => return Ok(false),
+        }
+
+        return Ok(true);
+    }
+
+    /// Prepares decompressing the image data at the first `IDAT` chunk, whose header has already been validated.
+    fn start_image_data(&mut self) -> Result<(), ParserError> {
+        if !self.decodes_rows || self.rows.is_some() {
+            return Ok(());
+        }
+
+        if let Some(header) = self.state.header() {
+            // The image data cannot decompress to more than the scanlines described by the header.
+            let limit =
+                filter::image_data_size(header).ok_or(ParserError::InvalidImageDataLength)?;
+            let is_cgbi = self.state.is_cgbi();
+            self.inflater = Some(StreamInflater::new(is_cgbi, limit));
+            self.rows = Some(RowDecoder::new(header, is_cgbi));
+        }
+
+        return Ok(());
+    }
+
+    /// Checks that the image data ended with the compressed stream and held every scanline of the image.
+    fn finish_image_data(&mut self) -> Result<(), ParserError> {
+        let Some(mut inflater) = self.inflater.take() else {
+            return Ok(());
+        };
+
+        inflater.write(&[], true)?;
+
+        if let Some(rows) = &mut self.rows {
+            let consumed = rows.decode(inflater.output(), &mut self.events)?;
+            inflater.consume(consumed);
+
+            if !rows.is_complete() {
+                return Err(ParserError::InvalidImageDataLength);
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+impl Default for StreamDecoder<'_> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// Represents the progress of reconstructing the scanlines of an image while its data is decompressed.
+struct RowDecoder {
+    layout: ImageLayout,
+    is_interlaced: bool,
+    is_cgbi: bool,
+    pass: usize,
+    y: u32,
+    previous: Vec<u8>,
+    current: Vec<u8>,
+    pixels: Vec<u8>,
+}
+
+impl RowDecoder {
+    fn new(header: &HeaderInfo, is_cgbi: bool) -> Self {
+        let layout = ImageLayout::from_header(header);
+        let stride = layout.stride();
+
+        return Self {
+            layout,
+            is_interlaced: header.interlace_method == InterlaceMethod::Adam7,
+            is_cgbi,
+            pass: 0,
+            y: 0,
+            previous: vec![0u8; stride],
+            current: vec![0u8; stride],
+            pixels: Vec::new(),
+        };
+    }
+
+    fn is_complete(&self) -> bool {
+        return match self.is_interlaced {
+            true => self.pass == ADAM7_PASSES.len(),
+            false => self.pass == 1,
+        };
+    }
+
+    /// Returns the width and height of the current pass, which is the whole image for non-interlaced images.
+    fn pass_size(&self) -> (u32, u32) {
+        return match self.is_interlaced {
+            true => interlace::pass_size(self.pass, self.layout.width, self.layout.height),
+            false => (self.layout.width, self.layout.height),
+        };
+    }
+
+    /// Reconstructs the complete scanlines at the start of the provided decompressed data.
+    ///
+    /// # Arguments
+    /// * `data` - The decompressed data that follows the scanlines reconstructed so far.
+    /// * `events` - The events to report the reconstructed scanlines to.
+    ///
+    /// # Returns
+    /// * `Result<usize, ParserError>` - The number of bytes reconstructed, or the error of an invalid scanline.
+    fn decode(
+        &mut self,
+        data: &[u8],
+        events: &mut VecDeque<DecodeEvent>,
+    ) -> Result<usize, ParserError> {
+        let mut position = 0;
+        let bits_per_pixel = self.layout.bits_per_pixel();
+        let bytes_per_pixel = bits_per_pixel.div_ceil(8);
+
+        while !self.is_complete() {
+            let (width, height) = self.pass_size();
+
+            // Empty passes are not transmitted at all, not even their filter type bytes.
+            if width == 0 || height == 0 {
+                self.finish_pass(events);
+                continue;
+            }
+
+            let stride = filter::stride(width, bits_per_pixel);
+            let Some(filtered) = data.get(position..position + stride + 1) else {
+                break;
+            };
+
+            filter::unfilter_scanline(
+                filtered,
+                &self.previous[..stride],
+                bytes_per_pixel,
+                &mut self.current[..stride],
+            )?;
+            position += stride + 1;
+
+            // The samples are restored on a copy, as the next scanline is unfiltered against the stored samples.
+            let mut row = self.current[..stride].to_vec();
+            if self.is_cgbi {
+                convert::restore_cgbi_samples(&self.layout, &mut row);
+            }
+
+            let y = self.store_row(&row, width);
+            events.push_back(DecodeEvent::Row {
+                pass: self.is_interlaced.then_some(self.pass as u8),
+                y,
+                data: row,
+            });
+
+            std::mem::swap(&mut self.previous, &mut self.current);
+            self.y += 1;
+
+            if self.y == height {
+                self.finish_pass(events);
+            }
+        }
+
+        return Ok(position);
+    }
+
+    /// Copies a reconstructed scanline of the current pass into the pixels, and returns the row it is placed in.
+    fn store_row(&mut self, row: &[u8], width: u32) -> u32 {
+        let stride = self.layout.stride();
+        if self.pixels.is_empty() {
+            self.pixels = vec![0u8; stride * self.layout.height as usize];
+        }
+
+        if !self.is_interlaced {
+            let y = self.y as usize;
+            self.pixels[y * stride..(y + 1) * stride].copy_from_slice(row);
+            return self.y;
+        }
+
+        let (x_start, y_start, x_step, y_step) = ADAM7_PASSES[self.pass];
+        let y = y_start + self.y * y_step;
+        let target = &mut self.pixels[y as usize * stride..(y as usize + 1) * stride];
+
+        for pass_x in 0..width as usize {
+            let x = (x_start + pass_x as u32 * x_step) as usize;
+            interlace::copy_pixel(row, pass_x, target, x, self.layout.bits_per_pixel());
+        }
+
+        return y;
+    }
+
+    fn finish_pass(&mut self, events: &mut VecDeque<DecodeEvent>) {
+        if self.is_interlaced {
+            events.push_back(DecodeEvent::PassComplete(self.pass as u8));
+        }
+
+        self.pass += 1;
+        self.y = 0;
+        self.previous.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::{self, EncoderOptions};
+
+    fn encode(interlace_method: InterlaceMethod) -> (Vec<u8>, Vec<u8>) {
+        let header = HeaderInfo {
+            width: 12,
+            height: 11,
+            bit_depth: 8,
+            color_type: ColorType::TrueColor,
+            compression_method: CompressionMethod::Deflate,
+            filter_method: FilterMethod::Adaptive,
+            interlace_method,
+        };
+        let pixels: Vec<u8> = (0..header.stride() * header.height as usize)
+            .map(|i| (i * 7 % 251) as u8)
+            .collect();
+        let options = EncoderOptions {
+            max_chunk_size: 64,
+            ..Default::default()
+        };
+
+        let mut png = Vec::new();
+        encoder::encode(&mut png, header, None, &pixels, &options).unwrap();
+        return (png, pixels);
+    }
+
+    /// Feeds the data one byte at a time, and returns every event with the number of bytes fed before it.
+    fn decode_bytewise(decoder: &mut StreamDecoder, png: &[u8]) -> Vec<(usize, DecodeEvent)> {
+        let mut events = Vec::new();
+        for fed in 0..=png.len() {
+            loop {
+                match decoder.next_event().unwrap() {
+                    DecodeEvent::NeedMoreData => break,
+                    event => events.push((fed, event)),
+                }
+            }
+
+            if fed < png.len() {
+                decoder.feed(&png[fed..fed + 1]);
+            }
+        }
+
+        return events;
+    }
+
+    #[test]
+    fn test_stream_decoder() {
+        let (png, pixels) = encode(InterlaceMethod::None);
+        let mut decoder = StreamDecoder::new();
+        let events = decode_bytewise(&mut decoder, &png);
+
+        assert!(matches!(
+            events[1].1,
+            DecodeEvent::Header(HeaderInfo { width: 12, .. })
+        ));
+
+        let rows: Vec<(usize, u32)> = events
+            .iter()
+            .filter_map(|(fed, event)| match event {
+                DecodeEvent::Row { pass: None, y, .. } => Some((*fed, *y)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            rows.iter().map(|row| row.1).collect::<Vec<_>>(),
+            (0..11).collect::<Vec<_>>()
+        );
+        // The rows are reported while the image data is still arriving.
+        assert!(rows[0].0 < rows[10].0);
+        assert!(rows[10].0 < png.len() - 12);
+
+        let Some((fed, DecodeEvent::End(info))) = events.last() else {
+            panic!("the stream did not end");
+        };
+        assert_eq!(*fed, png.len());
+        assert!(decoder.is_finished());
+        assert_eq!(decoder.pixels(), pixels);
+        assert_eq!(info.decode().unwrap().pixels, pixels);
+        assert_eq!(
+            info.image_data_spans.len(),
+            info.compressed_data.chunk_count as usize
+        );
+    }
+
+    #[test]
+    fn test_stream_decoder_interlaced() {
+        let (png, pixels) = encode(InterlaceMethod::Adam7);
+        let mut decoder = StreamDecoder::new();
+        let events = decode_bytewise(&mut decoder, &png);
+
+        let mut passes = Vec::new();
+        let mut rows = [0u32; 7];
+        for (_, event) in &events {
+            match event {
+                DecodeEvent::Row {
+                    pass: Some(pass),
+                    data,
+                    ..
+                } => {
+                    assert_eq!(passes.len(), *pass as usize);
+                    let (width, _) = interlace::pass_size(*pass as usize, 12, 11);
+                    assert_eq!(data.len(), width as usize * 3);
+                    rows[*pass as usize] += 1;
+                }
+                DecodeEvent::PassComplete(pass) => passes.push(*pass),
+                _ => {}
+            }
+        }
+
+        assert_eq!(passes, [0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(rows, [2, 2, 1, 3, 3, 6, 5]);
+        assert_eq!(decoder.pixels(), pixels);
+    }
+
+    #[test]
+    fn test_stream_decoder_errors() {
+        let (mut png, _) = encode(InterlaceMethod::None);
+
+        let mut decoder = StreamDecoder::new();
+        decoder.feed(&png[..png.len() - 1]);
+        while !matches!(decoder.next_event().unwrap(), DecodeEvent::NeedMoreData) {}
+        assert!(!decoder.is_finished());
+
+        // Corrupts the CRC of the header.
+        png[29] ^= 1;
+        let mut decoder = StreamDecoder::new();
+        decoder.feed(&png);
+        assert!(matches!(
+            decoder.next_event(),
+            Err(ParserError::CorruptedData)
+        ));
+    }
+}
@@ -0,0 +1,64 @@
+use std::io::Read;
+
+use crate::crc32;
+use crate::deflate::{self, CompressionLevel};
+use crate::spec::*;
+use crate::writer::Writer;
+
+/// Reads from a slice at most three bytes at a time without implementing `Seek`, and counts the bytes read.
+pub(crate) struct Stream<'a> {
+    pub(crate) data: &'a [u8],
+    pub(crate) read: usize,
+}
+
+impl<'a> Stream<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        return Self { data, read: 0 };
+    }
+}
+
+impl Read for Stream<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = &self.data[self.read..];
+        let count = remaining.len().min(buf.len()).min(3);
+        buf[..count].copy_from_slice(&remaining[..count]);
+        self.read += count;
+        return Ok(count);
+    }
+}
+
+/// Serializes a chunk with its length, type and CRC.
+pub(crate) fn chunk(chunk_type: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let type_and_data = [&chunk_type[..], data].concat();
+    return [
+        &(data.len() as u32).to_be_bytes()[..],
+        &type_and_data,
+        &crc32::compute(&type_and_data).to_be_bytes(),
+    ]
+    .concat();
+}
+
+/// Builds a 255x1 grayscale image whose stored image data is split into three `IDAT` chunks.
+pub(crate) fn split_png() -> Vec<u8> {
+    let pixels = (0..=255).collect::<Vec<u8>>();
+    let info = PngInfo::new(
+        HeaderInfo {
+            width: 255,
+            height: 1,
+            bit_depth: 8,
+            color_type: ColorType::Grayscale,
+            compression_method: CompressionMethod::Deflate,
+            filter_method: FilterMethod::Adaptive,
+            interlace_method: InterlaceMethod::None,
+        },
+        CompressedDataInfo {
+            chunk_count: 3,
+            chunk_lengths: Vec::new(),
+            data: deflate::compress(&pixels, CompressionLevel::Stored),
+        },
+    );
+
+    let mut png = Vec::new();
+    Writer::write(&mut png, &info).unwrap();
+    return png;
+}
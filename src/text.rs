@@ -0,0 +1,200 @@
+use crate::error::ParserError;
+use crate::spec::*;
+
+/// Describes the chunk that a text entry was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextSource {
+    /// An uncompressed Latin-1 `tEXt` chunk.
+    Text,
+    /// A compressed Latin-1 `zTXt` chunk.
+    CompressedText,
+    /// A (maybe compressed) UTF-8 `iTXt` chunk.
+    InternationalText,
+}
+
+/// Represents a decoded text entry regardless of the chunk it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEntry {
+    /// The keyword.
+    pub keyword: String,
+    /// The language tag, only present for `iTXt` chunks.
+    pub language_tag: Option<String>,
+    /// The translated keyword, only present for `iTXt` chunks.
+    pub translated_keyword: Option<String>,
+    /// The decoded text.
+    pub text: String,
+    /// The chunk that the entry was read from.
+    pub source: TextSource,
+}
+
+/// Refers to one of the textual chunks of an image before decoding it.
+#[derive(Clone, Copy)]
+enum TextChunk<'a> {
+    Text(&'a TextualDataInfo),
+    CompressedText(&'a CompressedTextualDataInfo),
+    InternationalText(&'a InternationalTextualDataInfo),
+}
+
+impl TextChunk<'_> {
+    fn keyword(&self) -> &str {
+        return match self {
+            TextChunk::Text(info) => &info.keyword,
+            TextChunk::CompressedText(info) => &info.keyword,
+            TextChunk::InternationalText(info) => &info.keyword,
+        };
+    }
+
+    fn decode(&self) -> Result<TextEntry, ParserError> {
+        let entry = match self {
+            TextChunk::Text(info) => TextEntry {
+                keyword: info.keyword.clone(),
+                language_tag: None,
+                translated_keyword: None,
+                text: info.text.clone(),
+                source: TextSource::Text,
+            },
+            TextChunk::CompressedText(info) => TextEntry {
+                keyword: info.keyword.clone(),
+                language_tag: None,
+                translated_keyword: None,
+                text: info.decompress_text()?,
+                source: TextSource::CompressedText,
+            },
+            TextChunk::InternationalText(info) => TextEntry {
+                keyword: info.keyword.clone(),
+                language_tag: Some(info.language_tag.clone()),
+                translated_keyword: Some(info.translated_keyword.clone()),
+                text: info.decode_text()?,
+                source: TextSource::InternationalText,
+            },
+        };
+
+        return Ok(entry);
+    }
+}
+
+impl PngInfo {
+    /// Returns an iterator that decodes the `tEXt`, `zTXt` and `iTXt` chunks in the order they were read.
+    ///
+    /// The chunks missing from `PngInfo::chunk_order`, such as those of info that was not read from a stream,
+    /// follow in the `tEXt`, `zTXt`, `iTXt` order. Each entry is decoded lazily, so an entry that fails to
+    /// decompress does not affect the others.
+    pub fn text_entries(&self) -> impl Iterator<Item = Result<TextEntry, ParserError>> + '_ {
+        return self.text_chunks().map(|chunk| chunk.decode());
+    }
+
+    /// Finds and decodes the first text entry with the provided keyword, in the order of `text_entries`.
+    ///
+    /// # Arguments
+    /// * `keyword` - The keyword to look up, compared case-sensitively.
+    ///
+    /// # Returns
+    /// * `Result<Option<TextEntry>, ParserError>` - The decoded entry, `None` if no chunk has the keyword, or the
+    ///   error that prevented decoding the entry.
+    pub fn find_text(&self, keyword: &str) -> Result<Option<TextEntry>, ParserError> {
+        return match self.text_chunks().find(|chunk| chunk.keyword() == keyword) {
+            Some(chunk) => Ok(Some(chunk.decode()?)),
+            None => Ok(None),
+        };
+    }
+
+    fn text_chunks(&self) -> impl Iterator<Item = TextChunk<'_>> {
+        let mut text = self.textual_data.iter().map(TextChunk::Text);
+        let mut compressed_text = self
+            .compressed_textual_data
+            .iter()
+            .map(TextChunk::CompressedText);
+        let mut international_text = self
+            .international_textual_data
+            .iter()
+            .map(TextChunk::InternationalText);
+
+        let mut chunks: Vec<TextChunk> = self
+            .chunk_order
+            .iter()
+            .filter_map(|&chunk_type| match u32::from_be_bytes(chunk_type) {
+                chunk_ids::tEXt => text.next(),
+                chunk_ids::zTXt => compressed_text.next(),
+                chunk_ids::iTXt => international_text.next(),
+                _ => None,
+            })
+            .collect();
+        chunks.extend(text.chain(compressed_text).chain(international_text));
+
+        return chunks.into_iter();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deflate::{self, CompressionLevel};
+
+    #[test]
+    fn test_text_entries_and_lookup() {
+        let header = HeaderInfo {
+            width: 1,
+            height: 1,
+            bit_depth: 8,
+            color_type: ColorType::Grayscale,
+            compression_method: CompressionMethod::Deflate,
+            filter_method: FilterMethod::Adaptive,
+            interlace_method: InterlaceMethod::None,
+        };
+        let mut info = PngInfo::new(
+            header,
+            CompressedDataInfo {
+                chunk_count: 0,
+                chunk_lengths: Vec::new(),
+                data: Vec::new(),
+            },
+        );
+        info.textual_data.push(TextualDataInfo {
+            keyword: String::from("Title"),
+            text: String::from("Sunset"),
+            position: ChunkPosition::BeforeImageData,
+        });
+        info.compressed_textual_data
+            .push(CompressedTextualDataInfo {
+                keyword: String::from("Description"),
+                compression_method: CompressionMethod::Deflate,
+                text: deflate::compress(b"A long description", CompressionLevel::Default),
+                position: ChunkPosition::BeforeImageData,
+            });
+        info.international_textual_data
+            .push(InternationalTextualDataInfo {
+                keyword: String::from("Title"),
+                is_compressed: false,
+                compression_method: CompressionMethod::Deflate,
+                language_tag: String::from("tr"),
+                translated_keyword: String::from("Başlık"),
+                text: "Gün batımı".as_bytes().to_vec(),
+                position: ChunkPosition::BeforeImageData,
+            });
+
+        let entries: Vec<TextEntry> = info.text_entries().map(|e| e.unwrap()).collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].text, "A long description");
+        assert_eq!(entries[2].language_tag.as_deref(), Some("tr"));
+        assert_eq!(entries[2].text, "Gün batımı");
+
+        let title = info.find_text("Title").unwrap().unwrap();
+        assert_eq!(title.source, TextSource::Text);
+        assert!(info.find_text("Author").unwrap().is_none());
+
+        // The international text was read first, and the recorded order lists only some of the chunks.
+        info.chunk_order = vec![*b"IHDR", *b"iTXt", *b"tEXt", *b"IDAT", *b"IEND"];
+        let sources: Vec<TextSource> = info.text_entries().map(|e| e.unwrap().source).collect();
+        assert_eq!(
+            sources,
+            [
+                TextSource::InternationalText,
+                TextSource::Text,
+                TextSource::CompressedText
+            ]
+        );
+
+        let title = info.find_text("Title").unwrap().unwrap();
+        assert_eq!(title.source, TextSource::InternationalText);
+    }
+}
@@ -1,6 +1,7 @@
-use std::io::{Read, Seek, SeekFrom};
+use std::borrow::Cow;
+use std::io::{Read, Seek, SeekFrom, Write};
 
-use crate::error::ParserError;
+use crate::error::{ParserError, WriterError};
 
 /// Converts the provided slice of data to vector using the provided projection function.
 pub fn to_chunked<const N: usize, F, To>(slice: &[u8], f: F) -> Vec<To>
@@ -27,12 +28,79 @@ pub fn to_f32(slice: &[u8]) -> f32 {
     return to_u32(slice) as f32 / 100000.0f32;
 }
 
+/// Converts the provided 32-bit floating point to its unsigned 32-bit integer representation.
+pub fn from_f32(value: f32) -> u32 {
+    return (value as f64 * 100000.0f64).round() as u32;
+}
+
 /// Converts the provided slice of data to a owned string.
 pub fn to_string(slice: &[u8]) -> String {
     return String::from_utf8(slice.to_vec()).unwrap();
 }
 
-/// Tries to get first null-terminated string out of the provided slice of data.
+/// Converts the provided slice of Latin-1 (ISO 8859-1) encoded data to a owned string.
+pub fn latin1_to_string(slice: &[u8]) -> String {
+    return slice.iter().map(|&b| b as char).collect();
+}
+
+/// Converts the provided slice of Latin-1 (ISO 8859-1) encoded data to a string, borrowing it when it is ASCII.
+pub fn latin1_to_str(slice: &[u8]) -> Cow<'_, str> {
+    if slice.is_ascii() {
+        return Cow::Borrowed(str::from_utf8(slice).unwrap());
+    }
+
+    return Cow::Owned(latin1_to_string(slice));
+}
+
+/// Tries to parse the provided slice of data as an ASCII floating-point number of the PNG specification.
+///
+/// The number consists of an optional sign, digits with an optional decimal point, and an optional exponent.
+pub fn parse_ascii_float(slice: &[u8]) -> Result<f64, ParserError> {
+    let mut index = 0;
+    let digits = |index: &mut usize| {
+        let start = *index;
+        while *index < slice.len() && slice[*index].is_ascii_digit() {
+            *index += 1;
+        }
+        *index - start
+    };
+
+    if index < slice.len() && (slice[index] == b'+' || slice[index] == b'-') {
+        index += 1;
+    }
+
+    let mut mantissa_digits = digits(&mut index);
+    if index < slice.len() && slice[index] == b'.' {
+        index += 1;
+        mantissa_digits += digits(&mut index);
+    }
+
+    if mantissa_digits == 0 {
+        return Err(ParserError::InvalidFieldValue);
+    }
+
+    if index < slice.len() && (slice[index] == b'e' || slice[index] == b'E') {
+        index += 1;
+        if index < slice.len() && (slice[index] == b'+' || slice[index] == b'-') {
+            index += 1;
+        }
+
+        if digits(&mut index) == 0 {
+            return Err(ParserError::InvalidFieldValue);
+        }
+    }
+
+    if index != slice.len() {
+        return Err(ParserError::InvalidFieldValue);
+    }
+
+    return match str::from_utf8(slice).unwrap().parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        _ => Err(ParserError::InvalidFieldValue),
+    };
+}
+
+/// Tries to get first null-terminated Latin-1 string out of the provided slice of data.
 pub fn get_string(slice: &[u8]) -> Result<String, ParserError> {
     let terminator = match slice.iter().position(|&b| b == 0) {
         Some(index) => index,
@@ -41,7 +109,25 @@ pub fn get_string(slice: &[u8]) -> Result<String, ParserError> {
         }
     };
 
-    return Ok(to_string(&slice[..terminator]));
+    return Ok(latin1_to_string(&slice[..terminator]));
+}
+
+/// Tries to split the provided slice of data into the part before the first null character and the part after it.
+pub fn split_null(slice: &[u8]) -> Result<(&[u8], &[u8]), ParserError> {
+    return match slice.iter().position(|&b| b == 0) {
+        Some(index) => Ok((&slice[..index], &slice[index + 1..])),
+        None => Err(ParserError::MissingNullTerminator),
+    };
+}
+
+/// Tries to split a null-terminated keyword off the provided slice of data and checks its length.
+pub fn split_keyword(slice: &[u8]) -> Result<(&[u8], &[u8]), ParserError> {
+    let (keyword, rest) = split_null(slice)?;
+    if keyword.is_empty() || keyword.len() > 79 {
+        return Err(ParserError::InvalidStringLength);
+    }
+
+    return Ok((keyword, rest));
 }
 
 /// Checks whether the provided string has length of valid range according to the PNG specification.
@@ -74,12 +160,17 @@ pub fn read_to<'a, Source: Read>(
 }
 
 /// Reads next n bytes from the provided source and returns a vector of bytes.
+///
+/// The vector grows with the data that is actually read, so a corrupt size cannot allocate memory up front.
 pub fn read_bytes<Source: Read>(source: &mut Source, size: usize) -> Result<Vec<u8>, ParserError> {
-    let mut bytes = vec![0u8; size];
-    match source.read_exact(&mut bytes) {
-        Ok(()) => Ok(bytes),
+    let mut bytes = Vec::new();
+    return match source.take(size as u64).read_to_end(&mut bytes) {
+        Ok(count) if count == size => Ok(bytes),
+        Ok(_) => Err(ParserError::IOError(
+            std::io::ErrorKind::UnexpectedEof.into(),
+        )),
         Err(e) => Err(ParserError::IOError(e)),
-    }
+    };
 }
 
 /// Seeks the position of the provided source.
@@ -89,3 +180,36 @@ pub fn seek<Source: Seek>(source: &mut Source, pos: i64) -> Result<(), ParserErr
         Err(e) => Err(ParserError::IOError(e)),
     }
 }
+
+/// Skips next n bytes of the provided source without buffering them.
+pub fn skip<Source: Read>(source: &mut Source, count: u64) -> Result<(), ParserError> {
+    return match std::io::copy(&mut source.take(count), &mut std::io::sink()) {
+        Ok(copied) if copied == count => Ok(()),
+        Ok(_) => Err(ParserError::IOError(
+            std::io::ErrorKind::UnexpectedEof.into(),
+        )),
+        Err(e) => Err(ParserError::IOError(e)),
+    };
+}
+
+/// Writes all of the provided bytes to the provided sink.
+pub fn write_bytes<Sink: Write>(sink: &mut Sink, bytes: &[u8]) -> Result<(), WriterError> {
+    match sink.write_all(bytes) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(WriterError::IOError(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ascii_float() {
+        assert_eq!(parse_ascii_float(b"1.5e+3").unwrap(), 1500.0);
+        assert_eq!(parse_ascii_float(b"-.25").unwrap(), -0.25);
+        assert!(parse_ascii_float(b"1.5e").is_err());
+        assert!(parse_ascii_float(b"inf").is_err());
+        assert!(parse_ascii_float(b".").is_err());
+    }
+}
@@ -0,0 +1,1054 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+
+use crate::crc32;
+use crate::error::WriterError;
+use crate::spec::*;
+use crate::utils;
+
+/// Represents a writer that serializes PNG data.
+pub struct Writer<'a, Sink: Write> {
+    sink: &'a mut Sink,
+    sequence_number: u32,
+    /// The serialized chunks with their types, which are written to the sink once they are all known.
+    chunks: Vec<(ChunkId, Vec<u8>)>,
+}
+
+impl<'a, Sink: Write> Writer<'a, Sink> {
+    /// Tries to write the provided PNG info to provided sink.
+    ///
+    /// Chunks are written in the order they were read while `PngInfo::chunk_order` lists exactly the chunks of the
+    /// info. Otherwise they are written in the order recommended by the PNG specification, with textual, unknown and
+    /// custom chunks at their recorded position. The image data is split as in the original stream when the chunk
+    /// lengths are known.
+    pub fn write(sink: &'a mut Sink, info: &PngInfo) -> Result<(), WriterError> {
+        let mut writer = Self {
+            sink,
+            sequence_number: 0,
+            chunks: Vec::new(),
+        };
+
+        writer.write_signature()?;
+        if let Some(cgbi) = &info.cgbi {
+            writer.write_chunk(chunk_ids::CgBI, &cgbi.flags.to_be_bytes())?;
+        }
+        writer.write_header(&info.header)?;
+
+        if let Some(coding_independent_code_points) = &info.coding_independent_code_points {
+            writer.write_cicp(coding_independent_code_points)?;
+        }
+        if let Some(mastering_display_color_volume) = &info.mastering_display_color_volume {
+            writer.write_mdcv(mastering_display_color_volume)?;
+        }
+        if let Some(content_light_level) = &info.content_light_level {
+            writer.write_clli(content_light_level)?;
+        }
+        if let Some(chromaticity) = &info.chromaticity {
+            writer.write_chrm(chromaticity)?;
+        }
+        if let Some(gamma) = &info.gamma {
+            writer.write_gama(gamma)?;
+        }
+        if let Some(icc_profile) = &info.icc_profile {
+            writer.write_iccp(icc_profile)?;
+        }
+        if let Some(standard_rgb) = &info.standard_rgb {
+            writer.write_srgb(standard_rgb)?;
+        }
+        if let Some(significant_bits) = &info.significant_bits {
+            writer.write_sbit(significant_bits)?;
+        }
+        writer.write_positioned_chunks(info, ChunkPosition::BeforePalette)?;
+        if let Some(palette) = &info.palette {
+            writer.write_plte(palette)?;
+        }
+        if let Some(transparency) = &info.transparency {
+            writer.write_trns(transparency)?;
+        }
+        if let Some(background) = &info.background {
+            writer.write_bkgd(background)?;
+        }
+        if let Some(palette_histogram) = &info.palette_histogram {
+            writer.write_hist(palette_histogram)?;
+        }
+        if let Some(physical_pixel_dimension) = &info.physical_pixel_dimension {
+            writer.write_phys(physical_pixel_dimension)?;
+        }
+        for suggested_palette in &info.suggested_palettes {
+            writer.write_splt(suggested_palette)?;
+        }
+        if let Some(last_modification) = &info.last_modification {
+            writer.write_time(last_modification)?;
+        }
+        if let Some(image_offset) = &info.image_offset {
+            writer.write_offs(image_offset)?;
+        }
+        if let Some(pixel_calibration) = &info.pixel_calibration {
+            writer.write_pcal(pixel_calibration)?;
+        }
+        if let Some(physical_scale) = &info.physical_scale {
+            writer.write_scal(physical_scale)?;
+        }
+        if let Some(stereo_image) = &info.stereo_image {
+            writer.write_ster(stereo_image)?;
+        }
+        for gif_graphic_control in &info.gif_graphic_controls {
+            writer.write_gifg(gif_graphic_control)?;
+        }
+        for gif_application_extension in &info.gif_application_extensions {
+            writer.write_gifx(gif_application_extension)?;
+        }
+        for gif_plain_text_extension in &info.gif_plain_text_extensions {
+            writer.write_gift(gif_plain_text_extension)?;
+        }
+        if let Some(exif) = &info.exif {
+            writer.write_chunk(chunk_ids::eXIf, &exif.data)?;
+        }
+        writer.write_positioned_chunks(info, ChunkPosition::BeforeImageData)?;
+
+        if let Some(animation_control) = &info.animation_control {
+            writer.write_actl(animation_control, &info.animation_frames)?;
+        }
+
+        // The frame control of the default image, if it is part of the animation, precedes the image data.
+        let mut frames = info.animation_frames.iter().peekable();
+        if let Some(frame) = frames.next_if(|frame| frame.data.is_none()) {
+            writer.write_fctl(&frame.control)?;
+        }
+
+        writer.write_idat(&info.compressed_data)?;
+
+        for frame in frames {
+            let Some(frame_data) = &frame.data else {
+                return Err(WriterError::InvalidFieldValue);
+            };
+
+            writer.write_fctl(&frame.control)?;
+            writer.write_fdat(frame_data)?;
+        }
+
+        writer.write_positioned_chunks(info, ChunkPosition::AfterImageData)?;
+        writer.write_chunk(chunk_ids::IEND, &[])?;
+
+        return writer.flush_chunks(&info.chunk_order);
+    }
+
+    /// Writes the serialized chunks to the sink in the provided order, or in the order they were serialized if the
+    /// provided order does not list exactly the serialized chunks.
+    fn flush_chunks(&mut self, chunk_order: &[[u8; 4]]) -> Result<(), WriterError> {
+        let chunks = std::mem::take(&mut self.chunks);
+
+        let mut serialized_types: Vec<ChunkId> = chunks.iter().map(|chunk| chunk.0).collect();
+        let mut recorded_types: Vec<ChunkId> = chunk_order
+            .iter()
+            .map(|&chunk_type| u32::from_be_bytes(chunk_type))
+            .collect();
+        serialized_types.sort_unstable();
+        recorded_types.sort_unstable();
+
+        if serialized_types != recorded_types {
+            for (_, chunk) in &chunks {
+                utils::write_bytes(self.sink, chunk)?;
+            }
+
+            return Ok(());
+        }
+
+        // The chunks of the same type keep the order they were serialized in, which is the order they were read in.
+        let mut chunks_by_type: HashMap<ChunkId, VecDeque<Vec<u8>>> = HashMap::new();
+        for (chunk_type, chunk) in chunks {
+            chunks_by_type
+                .entry(chunk_type)
+                .or_default()
+                .push_back(chunk);
+        }
+
+        for &chunk_type in chunk_order {
+            if let Some(chunk) = chunks_by_type
+                .get_mut(&u32::from_be_bytes(chunk_type))
+                .and_then(|chunks| chunks.pop_front())
+            {
+                utils::write_bytes(self.sink, &chunk)?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Writes the textual, unknown and custom chunks that were recorded at the provided position.
+    fn write_positioned_chunks(
+        &mut self,
+        info: &PngInfo,
+        position: ChunkPosition,
+    ) -> Result<(), WriterError> {
+        for textual_data in info.textual_data.iter().filter(|c| c.position == position) {
+            self.write_text(textual_data)?;
+        }
+        for compressed_textual_data in info
+            .compressed_textual_data
+            .iter()
+            .filter(|c| c.position == position)
+        {
+            self.write_ztxt(compressed_textual_data)?;
+        }
+        for international_textual_data in info
+            .international_textual_data
+            .iter()
+            .filter(|c| c.position == position)
+        {
+            self.write_itxt(international_textual_data)?;
+        }
+
+        // The chunks turned into values by handlers are written between the unknown chunks they were found between.
+        for index in 0..=info.unknown_chunks.len() {
+            for custom_chunk in info
+                .custom_chunks
+                .iter()
+                .filter(|c| c.position == position && c.unknown_chunk_index == index)
+            {
+                self.write_chunk(
+                    u32::from_be_bytes(custom_chunk.chunk_type),
+                    &custom_chunk.data,
+                )?;
+            }
+
+            match info.unknown_chunks.get(index) {
+                Some(unknown_chunk) if unknown_chunk.position == position => self.write_chunk(
+                    u32::from_be_bytes(unknown_chunk.chunk_type),
+                    &unknown_chunk.data,
+                )?,
+                _ => {}
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn write_signature(&mut self) -> Result<(), WriterError> {
+        return utils::write_bytes(self.sink, &SIGNATURE);
+    }
+
+    /// Serializes a chunk with its length, type and CRC computed over the type and data.
+    fn write_chunk(&mut self, chunk_type: ChunkId, data: &[u8]) -> Result<(), WriterError> {
+        if data.len() > i32::MAX as usize {
+            return Err(WriterError::InvalidChunkLength(chunk_type));
+        }
+
+        let mut chunk = Vec::with_capacity(12 + data.len());
+        chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        chunk.extend_from_slice(&chunk_type.to_be_bytes());
+        chunk.extend_from_slice(data);
+        let crc = crc32::compute(&chunk[4..]);
+        chunk.extend_from_slice(&crc.to_be_bytes());
+
+        self.chunks.push((chunk_type, chunk));
+
+        return Ok(());
+    }
+
+    fn write_header(&mut self, header: &HeaderInfo) -> Result<(), WriterError> {
+        let mut data = Vec::with_capacity(13);
+        data.extend_from_slice(&header.width.to_be_bytes());
+        data.extend_from_slice(&header.height.to_be_bytes());
+        data.push(header.bit_depth);
+        data.push(match header.color_type {
+            ColorType::Grayscale => 0,
+            ColorType::TrueColor => 2,
+            ColorType::IndexedColor => 3,
+            ColorType::GrayscaleAlpha => 4,
+            ColorType::TrueColorAlpha => 6,
+        });
+        data.push(match header.compression_method {
+            CompressionMethod::Deflate => 0,
+        });
+        data.push(match header.filter_method {
+            FilterMethod::Adaptive => 0,
+        });
+        data.push(match header.interlace_method {
+            InterlaceMethod::None => 0,
+            InterlaceMethod::Adam7 => 1,
+        });
+
+        return self.write_chunk(chunk_ids::IHDR, &data);
+    }
+
+    fn write_plte(&mut self, palette: &PaletteInfo) -> Result<(), WriterError> {
+        if palette.entries.is_empty() || palette.entries.len() > 256 {
+            return Err(WriterError::InvalidChunkLength(chunk_ids::PLTE));
+        }
+
+        let data: Vec<u8> = palette
+            .entries
+            .iter()
+            .flat_map(|&(r, g, b)| [r, g, b])
+            .collect();
+
+        return self.write_chunk(chunk_ids::PLTE, &data);
+    }
+
+    /// Splits the compressed data into the recorded number of consecutive `IDAT` chunks.
+    fn write_idat(&mut self, compressed_data: &CompressedDataInfo) -> Result<(), WriterError> {
+        if compressed_data.data.is_empty() {
+            return Err(WriterError::InvalidChunkLength(chunk_ids::IDAT));
+        }
+
+        let chunks = split_data(
+            &compressed_data.data,
+            compressed_data.chunk_count,
+            &compressed_data.chunk_lengths,
+        );
+        for data in chunks {
+            self.write_chunk(chunk_ids::IDAT, data)?;
+        }
+
+        return Ok(());
+    }
+
+    fn write_actl(
+        &mut self,
+        animation_control: &AnimationControlInfo,
+        frames: &[FrameInfo],
+    ) -> Result<(), WriterError> {
+        if animation_control.frame_count == 0
+            || animation_control.frame_count as usize != frames.len()
+        {
+            return Err(WriterError::InvalidFieldValue);
+        }
+
+        let mut data = Vec::with_capacity(8);
+        data.extend_from_slice(&animation_control.frame_count.to_be_bytes());
+        data.extend_from_slice(&animation_control.play_count.to_be_bytes());
+
+        return self.write_chunk(chunk_ids::acTL, &data);
+    }
+
+    /// Writes a frame control with the next sequence number, regardless of the stored one.
+    fn write_fctl(&mut self, control: &FrameControlInfo) -> Result<(), WriterError> {
+        let mut data = Vec::with_capacity(26);
+        data.extend_from_slice(&self.next_sequence_number().to_be_bytes());
+        data.extend_from_slice(&control.width.to_be_bytes());
+        data.extend_from_slice(&control.height.to_be_bytes());
+        data.extend_from_slice(&control.x_offset.to_be_bytes());
+        data.extend_from_slice(&control.y_offset.to_be_bytes());
+        data.extend_from_slice(&control.delay_numerator.to_be_bytes());
+        data.extend_from_slice(&control.delay_denominator.to_be_bytes());
+        data.push(match control.dispose_op {
+            DisposeOp::None => 0,
+            DisposeOp::Background => 1,
+            DisposeOp::Previous => 2,
+        });
+        data.push(match control.blend_op {
+            BlendOp::Source => 0,
+            BlendOp::Over => 1,
+        });
+
+        return self.write_chunk(chunk_ids::fcTL, &data);
+    }
+
+    fn write_fdat(&mut self, frame_data: &FrameDataInfo) -> Result<(), WriterError> {
+        if frame_data.data.is_empty() {
+            return Err(WriterError::InvalidChunkLength(chunk_ids::fdAT));
+        }
+
+        let chunks = split_data(
+            &frame_data.data,
+            frame_data.chunk_count,
+            &frame_data.chunk_lengths,
+        );
+        for chunk in chunks {
+            let mut data = Vec::with_capacity(4 + chunk.len());
+            data.extend_from_slice(&self.next_sequence_number().to_be_bytes());
+            data.extend_from_slice(chunk);
+
+            self.write_chunk(chunk_ids::fdAT, &data)?;
+        }
+
+        return Ok(());
+    }
+
+    fn next_sequence_number(&mut self) -> u32 {
+        let sequence_number = self.sequence_number;
+        self.sequence_number += 1;
+        return sequence_number;
+    }
+
+    fn write_trns(&mut self, transparency: &TransparencyInfo) -> Result<(), WriterError> {
+        let data = match &transparency.transparency {
+            TransparencyVariant::Grayscale(gray) => gray.to_be_bytes().to_vec(),
+            TransparencyVariant::TrueColor(r, g, b) => {
+                [r.to_be_bytes(), g.to_be_bytes(), b.to_be_bytes()].concat()
+            }
+            TransparencyVariant::IndexedColor(alphas) => alphas.clone(),
+        };
+
+        return self.write_chunk(chunk_ids::tRNS, &data);
+    }
+
+    fn write_gama(&mut self, gamma: &GammaInfo) -> Result<(), WriterError> {
+        return self.write_chunk(chunk_ids::gAMA, &utils::from_f32(gamma.gamma).to_be_bytes());
+    }
+
+    fn write_chrm(&mut self, chromaticity: &ChromaticityInfo) -> Result<(), WriterError> {
+        let points = [
+            chromaticity.white_point,
+            chromaticity.red,
+            chromaticity.green,
+            chromaticity.blue,
+        ];
+
+        let data: Vec<u8> = points
+            .iter()
+            .flat_map(|&(x, y)| [utils::from_f32(x), utils::from_f32(y)])
+            .flat_map(u32::to_be_bytes)
+            .collect();
+
+        return self.write_chunk(chunk_ids::cHRM, &data);
+    }
+
+    fn write_offs(&mut self, image_offset: &ImageOffsetInfo) -> Result<(), WriterError> {
+        let mut data = Vec::with_capacity(9);
+        data.extend_from_slice(&image_offset.position.0.to_be_bytes());
+        data.extend_from_slice(&image_offset.position.1.to_be_bytes());
+        data.push(match image_offset.unit {
+            OffsetUnit::Pixel => 0,
+            OffsetUnit::Micrometer => 1,
+        });
+
+        return self.write_chunk(chunk_ids::oFFs, &data);
+    }
+
+    fn write_pcal(&mut self, pixel_calibration: &PixelCalibrationInfo) -> Result<(), WriterError> {
+        let equation_type = pixel_calibration.equation_type;
+        if pixel_calibration.original_zero == pixel_calibration.original_max
+            || pixel_calibration.parameters.len() != equation_type.parameter_count()
+        {
+            return Err(WriterError::InvalidFieldValue);
+        }
+
+        let mut data = Vec::new();
+        write_string(&mut data, &pixel_calibration.name)?;
+        data.extend_from_slice(&pixel_calibration.original_zero.to_be_bytes());
+        data.extend_from_slice(&pixel_calibration.original_max.to_be_bytes());
+        data.push(match equation_type {
+            EquationType::Linear => 0,
+            EquationType::Exponential => 1,
+            EquationType::ArbitraryExponential => 2,
+            EquationType::Hyperbolic => 3,
+        });
+        data.push(equation_type.parameter_count() as u8);
+        write_latin1(&mut data, &pixel_calibration.unit_name)?;
+        data.push(0);
+
+        for (index, &parameter) in pixel_calibration.parameters.iter().enumerate() {
+            if index > 0 {
+                data.push(0);
+            }
+            write_float(&mut data, parameter)?;
+        }
+
+        return self.write_chunk(chunk_ids::pCAL, &data);
+    }
+
+    fn write_scal(&mut self, physical_scale: &PhysicalScaleInfo) -> Result<(), WriterError> {
+        if physical_scale.pixel_width <= 0.0 || physical_scale.pixel_height <= 0.0 {
+            return Err(WriterError::InvalidFieldValue);
+        }
+
+        let mut data = vec![match physical_scale.unit {
+            ScaleUnit::Meter => 1,
+            ScaleUnit::Radian => 2,
+        }];
+        write_float(&mut data, physical_scale.pixel_width)?;
+        data.push(0);
+        write_float(&mut data, physical_scale.pixel_height)?;
+
+        return self.write_chunk(chunk_ids::sCAL, &data);
+    }
+
+    fn write_ster(&mut self, stereo_image: &StereoImageInfo) -> Result<(), WriterError> {
+        let layout = match stereo_image.layout {
+            StereoLayout::CrossFuse => 0,
+            StereoLayout::DivergingFuse => 1,
+        };
+
+        return self.write_chunk(chunk_ids::sTER, &[layout]);
+    }
+
+    fn write_gifg(&mut self, graphic_control: &GifGraphicControlInfo) -> Result<(), WriterError> {
+        let mut data = vec![
+            graphic_control.disposal_method,
+            graphic_control.user_input as u8,
+        ];
+        data.extend_from_slice(&graphic_control.delay_time.to_be_bytes());
+
+        return self.write_chunk(chunk_ids::gIFg, &data);
+    }
+
+    fn write_gifx(
+        &mut self,
+        application_extension: &GifApplicationExtensionInfo,
+    ) -> Result<(), WriterError> {
+        let data = [
+            &application_extension.application_identifier[..],
+            &application_extension.authentication_code,
+            &application_extension.data,
+        ]
+        .concat();
+
+        return self.write_chunk(chunk_ids::gIFx, &data);
+    }
+
+    fn write_gift(
+        &mut self,
+        plain_text_extension: &GifPlainTextExtensionInfo,
+    ) -> Result<(), WriterError> {
+        let (r, g, b) = plain_text_extension.foreground;
+        let (br, bg, bb) = plain_text_extension.background;
+
+        let mut data = Vec::with_capacity(24 + plain_text_extension.text.len());
+        data.extend_from_slice(&plain_text_extension.grid_position.0.to_be_bytes());
+        data.extend_from_slice(&plain_text_extension.grid_position.1.to_be_bytes());
+        data.extend_from_slice(&plain_text_extension.grid_size.0.to_be_bytes());
+        data.extend_from_slice(&plain_text_extension.grid_size.1.to_be_bytes());
+        data.extend_from_slice(&[
+            plain_text_extension.cell_size.0,
+            plain_text_extension.cell_size.1,
+            r,
+            g,
+            b,
+            br,
+            bg,
+            bb,
+        ]);
+        write_latin1(&mut data, &plain_text_extension.text)?;
+
+        return self.write_chunk(chunk_ids::gIFt, &data);
+    }
+
+    fn write_cicp(
+        &mut self,
+        code_points: &CodingIndependentCodePointsInfo,
+    ) -> Result<(), WriterError> {
+        if code_points.matrix_coefficients != 0 {
+            return Err(WriterError::InvalidFieldValue);
+        }
+
+        let data = [
+            code_points.color_primaries,
+            code_points.transfer_function,
+            code_points.matrix_coefficients,
+            code_points.is_full_range as u8,
+        ];
+
+        return self.write_chunk(chunk_ids::cICP, &data);
+    }
+
+    fn write_mdcv(
+        &mut self,
+        color_volume: &MasteringDisplayColorVolumeInfo,
+    ) -> Result<(), WriterError> {
+        let points = [
+            color_volume.red,
+            color_volume.green,
+            color_volume.blue,
+            color_volume.white_point,
+        ];
+
+        let mut data: Vec<u8> = points
+            .iter()
+            .flat_map(|&(x, y)| [x, y])
+            .flat_map(|value| ((value as f64 * 50000.0).round() as u16).to_be_bytes())
+            .collect();
+
+        for luminance in [color_volume.max_luminance, color_volume.min_luminance] {
+            data.extend_from_slice(&((luminance as f64 * 10000.0).round() as u32).to_be_bytes());
+        }
+
+        return self.write_chunk(chunk_ids::mDCv, &data);
+    }
+
+    fn write_clli(&mut self, light_level: &ContentLightLevelInfo) -> Result<(), WriterError> {
+        let data: Vec<u8> = [
+            light_level.max_content_light_level,
+            light_level.max_frame_average_light_level,
+        ]
+        .iter()
+        .flat_map(|&level| ((level as f64 * 10000.0).round() as u32).to_be_bytes())
+        .collect();
+
+        return self.write_chunk(chunk_ids::cLLi, &data);
+    }
+
+    fn write_srgb(&mut self, standard_rgb: &StandardRGBInfo) -> Result<(), WriterError> {
+        let rendering_intent = match standard_rgb.rendering_intent {
+            RenderingIntent::Perceptual => 0,
+            RenderingIntent::RelativeColorimetric => 1,
+            RenderingIntent::Saturation => 2,
+            RenderingIntent::AbsoluteColorimetric => 3,
+        };
+
+        return self.write_chunk(chunk_ids::sRGB, &[rendering_intent]);
+    }
+
+    fn write_iccp(&mut self, icc_profile: &ICCProfileInfo) -> Result<(), WriterError> {
+        let mut data = Vec::new();
+        write_string(&mut data, &icc_profile.name)?;
+        data.push(compression_method_byte(&icc_profile.compression_method));
+        data.extend_from_slice(&icc_profile.compressed_profile_data);
+
+        return self.write_chunk(chunk_ids::iCCP, &data);
+    }
+
+    fn write_text(&mut self, textual_data: &TextualDataInfo) -> Result<(), WriterError> {
+        let mut data = Vec::new();
+        write_string(&mut data, &textual_data.keyword)?;
+        write_latin1(&mut data, &textual_data.text)?;
+
+        return self.write_chunk(chunk_ids::tEXt, &data);
+    }
+
+    fn write_ztxt(
+        &mut self,
+        compressed_textual_data: &CompressedTextualDataInfo,
+    ) -> Result<(), WriterError> {
+        let mut data = Vec::new();
+        write_string(&mut data, &compressed_textual_data.keyword)?;
+        data.push(compression_method_byte(
+            &compressed_textual_data.compression_method,
+        ));
+        data.extend_from_slice(&compressed_textual_data.text);
+
+        return self.write_chunk(chunk_ids::zTXt, &data);
+    }
+
+    fn write_itxt(
+        &mut self,
+        international_textual_data: &InternationalTextualDataInfo,
+    ) -> Result<(), WriterError> {
+        let mut data = Vec::new();
+        write_string(&mut data, &international_textual_data.keyword)?;
+        data.push(international_textual_data.is_compressed as u8);
+        data.push(compression_method_byte(
+            &international_textual_data.compression_method,
+        ));
+        data.extend_from_slice(international_textual_data.language_tag.as_bytes());
+        data.push(0);
+        data.extend_from_slice(international_textual_data.translated_keyword.as_bytes());
+        data.push(0);
+        data.extend_from_slice(&international_textual_data.text);
+
+        return self.write_chunk(chunk_ids::iTXt, &data);
+    }
+
+    fn write_bkgd(&mut self, background: &BackgroundInfo) -> Result<(), WriterError> {
+        let data = match &background.background {
+            BackgroundVariant::Grayscale(gray) => gray.to_be_bytes().to_vec(),
+            BackgroundVariant::TrueColor(r, g, b) => {
+                [r.to_be_bytes(), g.to_be_bytes(), b.to_be_bytes()].concat()
+            }
+            BackgroundVariant::IndexedColor(index) => vec![*index],
+        };
+
+        return self.write_chunk(chunk_ids::bKGD, &data);
+    }
+
+    fn write_phys(
+        &mut self,
+        physical_pixel_dimension: &PhysicalPixelDimensionInfo,
+    ) -> Result<(), WriterError> {
+        let (x, y) = physical_pixel_dimension.pixels_per_unit;
+
+        let mut data = Vec::with_capacity(9);
+        data.extend_from_slice(&x.to_be_bytes());
+        data.extend_from_slice(&y.to_be_bytes());
+        data.push(match physical_pixel_dimension.unit_specifier {
+            PhysicalUnitSpecifier::Unknown => 0,
+            PhysicalUnitSpecifier::Meter => 1,
+        });
+
+        return self.write_chunk(chunk_ids::pHYs, &data);
+    }
+
+    fn write_sbit(&mut self, significant_bits: &SignificantBitsInfo) -> Result<(), WriterError> {
+        let data = match significant_bits.significant_bits {
+            SignificantBitsVariant::Grayscale(gray) => vec![gray],
+            SignificantBitsVariant::TrueColor(r, g, b) => vec![r, g, b],
+            SignificantBitsVariant::IndexedColor(r, g, b) => vec![r, g, b],
+            SignificantBitsVariant::GrayscaleAlpha(gray, a) => vec![gray, a],
+            SignificantBitsVariant::TrueColorAlpha(r, g, b, a) => vec![r, g, b, a],
+        };
+
+        return self.write_chunk(chunk_ids::sBIT, &data);
+    }
+
+    fn write_splt(&mut self, suggested_palette: &SuggestedPaletteInfo) -> Result<(), WriterError> {
+        let mut data = Vec::new();
+        write_string(&mut data, &suggested_palette.name)?;
+        data.push(suggested_palette.sample_depth);
+
+        for entry in &suggested_palette.entries {
+            let samples = [entry.red, entry.green, entry.blue, entry.alpha];
+            match suggested_palette.sample_depth {
+                8 => data.extend(samples.iter().map(|&sample| sample as u8)),
+                16 => data.extend(samples.iter().flat_map(|sample| sample.to_be_bytes())),
+                _ => return Err(WriterError::InvalidFieldValue),
+            }
+            data.extend_from_slice(&entry.frequency.to_be_bytes());
+        }
+
+        return self.write_chunk(chunk_ids::sPLT, &data);
+    }
+
+    fn write_hist(&mut self, palette_histogram: &PaletteHistogramInfo) -> Result<(), WriterError> {
+        let data: Vec<u8> = palette_histogram
+            .entries
+            .iter()
+            .flat_map(|entry| entry.to_be_bytes())
+            .collect();
+
+        return self.write_chunk(chunk_ids::hIST, &data);
+    }
+
+    fn write_time(&mut self, last_modification: &LastModificationInfo) -> Result<(), WriterError> {
+        let mut data = Vec::with_capacity(7);
+        data.extend_from_slice(&last_modification.year.to_be_bytes());
+        data.extend_from_slice(&[
+            last_modification.month,
+            last_modification.day,
+            last_modification.hour,
+            last_modification.minute,
+            last_modification.second,
+        ]);
+
+        return self.write_chunk(chunk_ids::tIME, &data);
+    }
+}
+
+/// Appends the provided keyword/name in Latin-1 with its null-terminator after checking its length.
+fn write_string(data: &mut Vec<u8>, s: &str) -> Result<(), WriterError> {
+    let length = s.chars().count();
+    if length == 0 || length > 79 {
+        return Err(WriterError::InvalidStringLength);
+    }
+
+    write_latin1(data, s)?;
+    data.push(0);
+
+    return Ok(());
+}
+
+/// Appends the provided text in Latin-1 after checking that every character is representable.
+fn write_latin1(data: &mut Vec<u8>, s: &str) -> Result<(), WriterError> {
+    for c in s.chars() {
+        if c == '\0' || c as u32 > 0xff {
+            return Err(WriterError::InvalidFieldValue);
+        }
+
+        data.push(c as u8);
+    }
+
+    return Ok(());
+}
+
+/// Appends the provided number as an ASCII floating-point number.
+fn write_float(data: &mut Vec<u8>, value: f64) -> Result<(), WriterError> {
+    if !value.is_finite() {
+        return Err(WriterError::InvalidFieldValue);
+    }
+
+    data.extend_from_slice(value.to_string().as_bytes());
+
+    return Ok(());
+}
+
+/// Splits non-empty data into the chunks it was read from.
+///
+/// The recorded lengths are only reused while they still describe the data, and otherwise the data is split into
+/// `chunk_count` chunks whose lengths differ by at most one byte.
+///
+/// # Arguments
+/// * `data` - The data to split.
+/// * `chunk_count` - The number of chunks the data was read from.
+/// * `chunk_lengths` - The length of each chunk the data was read from.
+///
+/// # Returns
+/// * `Vec<&[u8]>` - The data of each chunk in order.
+fn split_data<'d>(data: &'d [u8], chunk_count: u32, chunk_lengths: &[u32]) -> Vec<&'d [u8]> {
+    let total_length: u64 = chunk_lengths.iter().map(|&length| length as u64).sum();
+    if total_length == data.len() as u64 && !chunk_lengths.contains(&0) {
+        let mut rest = data;
+        return chunk_lengths
+            .iter()
+            .map(|&length| {
+                let (chunk, next) = rest.split_at(length as usize);
+                rest = next;
+                chunk
+            })
+            .collect();
+    }
+
+    let chunk_count = (chunk_count as usize).clamp(1, data.len());
+    let (chunk_size, remainder) = (data.len() / chunk_count, data.len() % chunk_count);
+
+    let mut rest = data;
+    return (0..chunk_count)
+        .map(|index| {
+            let length = chunk_size + (index < remainder) as usize;
+            let (chunk, next) = rest.split_at(length);
+            rest = next;
+            chunk
+        })
+        .collect();
+}
+
+fn compression_method_byte(compression_method: &CompressionMethod) -> u8 {
+    return match compression_method {
+        CompressionMethod::Deflate => 0,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::deflate::{self, CompressionLevel};
+    use crate::encoder::{self, AnimationFrame};
+    use crate::parser::Parser;
+    use crate::test_utils::chunk;
+
+    /// A 2x2 indexed-color image with `gAMA`, `PLTE`, `tRNS` and `tEXt` chunks in the recommended order.
+    const INDEXED_PNG: [u8; 140] = [
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x02, 0x03, 0x00, 0x00, 0x00, 0x0f,
+        0xd8, 0xe5, 0xb7, 0x00, 0x00, 0x00, 0x04, 0x67, 0x41, 0x4d, 0x41, 0x00, 0x00, 0xb1, 0x8f,
+        0x0b, 0xfc, 0x61, 0x05, 0x00, 0x00, 0x00, 0x06, 0x50, 0x4c, 0x54, 0x45, 0xff, 0x00, 0x00,
+        0x00, 0xff, 0x00, 0xd2, 0x87, 0xef, 0x71, 0x00, 0x00, 0x00, 0x01, 0x74, 0x52, 0x4e, 0x53,
+        0x80, 0xad, 0x5e, 0x5b, 0x46, 0x00, 0x00, 0x00, 0x0c, 0x74, 0x45, 0x58, 0x74, 0x54, 0x69,
+        0x74, 0x6c, 0x65, 0x00, 0x70, 0x6e, 0x67, 0x2d, 0x72, 0x73, 0xd6, 0xd4, 0x67, 0xd1, 0x00,
+        0x00, 0x00, 0x0c, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0x10, 0x60, 0x70, 0x00, 0x00,
+        0x00, 0x74, 0x00, 0x51, 0x60, 0x0d, 0x8d, 0x46, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e,
+        0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    #[test]
+    fn test_parse_write_round_trip() {
+        let info = Parser::parse(&mut Cursor::new(&INDEXED_PNG)).unwrap();
+
+        let mut output = Vec::new();
+        Writer::write(&mut output, &info).unwrap();
+
+        assert_eq!(output, INDEXED_PNG);
+    }
+
+    #[test]
+    fn test_non_canonical_order_round_trip() {
+        let header = [0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0];
+        let chromaticity: Vec<u8> = [31270u32, 32900, 64000, 33000, 30000, 60000, 15000, 6000]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect();
+        let compressed_text = [
+            &b"Comment\0\0"[..],
+            &deflate::compress(b"compressed", CompressionLevel::Default),
+        ]
+        .concat();
+
+        // The gamma precedes the chromaticity, and the textual chunks of different types are interleaved.
+        let png = [
+            SIGNATURE.to_vec(),
+            chunk(b"IHDR", &header),
+            chunk(b"gAMA", &45455u32.to_be_bytes()),
+            chunk(b"tEXt", b"Title\0first"),
+            chunk(b"cHRM", &chromaticity),
+            chunk(b"zTXt", &compressed_text),
+            chunk(b"tEXt", b"Author\0second"),
+            chunk(
+                b"IDAT",
+                &deflate::compress(&[0, 0x80], CompressionLevel::Default),
+            ),
+            chunk(b"IEND", &[]),
+        ]
+        .concat();
+
+        let mut info = Parser::parse(&mut Cursor::new(&png)).unwrap();
+        let mut output = Vec::new();
+        Writer::write(&mut output, &info).unwrap();
+        assert_eq!(output, png);
+
+        // Without a recorded order that matches the chunks, the recommended order is used.
+        info.chunk_order.clear();
+        let mut output = Vec::new();
+        Writer::write(&mut output, &info).unwrap();
+        let find = |chunk_type: &[u8]| output.windows(4).position(|w| w == chunk_type).unwrap();
+        assert!(find(b"cHRM") < find(b"gAMA"));
+        assert_eq!(
+            Parser::parse(&mut Cursor::new(&output))
+                .unwrap()
+                .chunk_order
+                .len(),
+            8
+        );
+    }
+
+    #[test]
+    fn test_extension_chunks_round_trip() {
+        let mut info = Parser::parse(&mut Cursor::new(&INDEXED_PNG)).unwrap();
+        info.image_offset = Some(ImageOffsetInfo {
+            position: (-12, 40),
+            unit: OffsetUnit::Micrometer,
+        });
+        info.pixel_calibration = Some(PixelCalibrationInfo {
+            name: String::from("Température"),
+            original_zero: 0,
+            original_max: 1000,
+            equation_type: EquationType::Linear,
+            unit_name: String::from("°C"),
+            parameters: vec![-40.0, 125.0],
+        });
+        info.physical_scale = Some(PhysicalScaleInfo {
+            unit: ScaleUnit::Meter,
+            pixel_width: 0.5,
+            pixel_height: 1.25e-6,
+        });
+        info.gif_graphic_controls.push(GifGraphicControlInfo {
+            disposal_method: 2,
+            user_input: false,
+            delay_time: 10,
+        });
+
+        let mut output = Vec::new();
+        Writer::write(&mut output, &info).unwrap();
+        let info = Parser::parse(&mut Cursor::new(&output)).unwrap();
+
+        assert_eq!(info.image_offset.unwrap().position, (-12, 40));
+        let pixel_calibration = info.pixel_calibration.unwrap();
+        assert_eq!(pixel_calibration.name, "Température");
+        assert_eq!(pixel_calibration.unit_name, "°C");
+        assert_eq!(pixel_calibration.physical_value(255, 255), 85.0);
+        assert_eq!(info.physical_scale.unwrap().pixel_height, 1.25e-6);
+        assert_eq!(info.gif_graphic_controls[0].delay_time, 10);
+    }
+
+    #[test]
+    fn test_chunk_positions_round_trip() {
+        let data = deflate::compress(&[0, 0x40, 0, 0x80], CompressionLevel::Default);
+        let mut info = PngInfo::new(
+            HeaderInfo {
+                width: 1,
+                height: 2,
+                bit_depth: 8,
+                color_type: ColorType::Grayscale,
+                compression_method: CompressionMethod::Deflate,
+                filter_method: FilterMethod::Adaptive,
+                interlace_method: InterlaceMethod::None,
+            },
+            CompressedDataInfo {
+                chunk_count: 2,
+                chunk_lengths: vec![3, data.len() as u32 - 3],
+                data,
+            },
+        );
+        info.textual_data.push(TextualDataInfo {
+            keyword: String::from("Comment"),
+            text: String::from("after"),
+            position: ChunkPosition::AfterImageData,
+        });
+        info.unknown_chunks.push(UnknownChunkInfo {
+            chunk_type: *b"prVt",
+            data: vec![1, 2, 3],
+            position: ChunkPosition::BeforePalette,
+        });
+
+        let mut output = Vec::new();
+        Writer::write(&mut output, &info).unwrap();
+
+        let find = |chunk_type: &[u8]| output.windows(4).rposition(|w| w == chunk_type).unwrap();
+        assert!(find(b"tEXt") > find(b"IDAT"));
+
+        let parsed = Parser::parse(&mut Cursor::new(&output)).unwrap();
+        assert_eq!(
+            parsed.compressed_data.chunk_lengths,
+            info.compressed_data.chunk_lengths
+        );
+        assert_eq!(
+            parsed.textual_data[0].position,
+            ChunkPosition::AfterImageData
+        );
+        assert_eq!(
+            parsed.unknown_chunks[0].position,
+            ChunkPosition::BeforePalette
+        );
+
+        let mut rewritten = Vec::new();
+        Writer::write(&mut rewritten, &parsed).unwrap();
+        assert_eq!(rewritten, output);
+    }
+
+    #[test]
+    fn test_frame_data_chunks_round_trip() {
+        let frames: Vec<Vec<u8>> = (0..2u8)
+            .map(|step| (0..16 * 4).map(|i| i as u8 ^ step).collect())
+            .collect();
+        let frames: Vec<AnimationFrame> = frames
+            .iter()
+            .map(|pixels| AnimationFrame {
+                pixels,
+                delay_numerator: 1,
+                delay_denominator: 10,
+            })
+            .collect();
+        let mut png = Vec::new();
+        encoder::encode_animation(&mut png, 4, 4, &frames, 0, &Default::default()).unwrap();
+
+        // Splits the frame data into uneven chunks, as another encoder may have.
+        let mut info = Parser::parse(&mut Cursor::new(&png)).unwrap();
+        let frame_data = info.animation_frames[1].data.as_mut().unwrap();
+        frame_data.chunk_count = 2;
+        frame_data.chunk_lengths = vec![1, frame_data.data.len() as u32 - 1];
+        let chunk_lengths = frame_data.chunk_lengths.clone();
+
+        let mut output = Vec::new();
+        Writer::write(&mut output, &info).unwrap();
+        let parsed = Parser::parse(&mut Cursor::new(&output)).unwrap();
+        let parsed_frame_data = parsed.animation_frames[1].data.as_ref().unwrap();
+        assert_eq!(parsed_frame_data.chunk_lengths, chunk_lengths);
+
+        let mut rewritten = Vec::new();
+        Writer::write(&mut rewritten, &parsed).unwrap();
+        assert_eq!(rewritten, output);
+    }
+
+    #[test]
+    fn test_split_data() {
+        let data = [0u8; 9];
+        let lengths = |chunks: Vec<&[u8]>| chunks.iter().map(|c| c.len()).collect::<Vec<_>>();
+
+        assert_eq!(lengths(split_data(&data, 2, &[7, 2])), [7, 2]);
+        // Lengths that no longer describe the data are replaced by the recorded number of chunks.
+        assert_eq!(lengths(split_data(&data, 4, &[7, 3])), [3, 2, 2, 2]);
+        assert_eq!(lengths(split_data(&data, 4, &[])), [3, 2, 2, 2]);
+        assert_eq!(lengths(split_data(&data, 0, &[])), [9]);
+        assert_eq!(lengths(split_data(&data[..2], 4, &[])), [1, 1]);
+    }
+
+    #[test]
+    fn test_write_latin1_text() {
+        let mut info = Parser::parse(&mut Cursor::new(&INDEXED_PNG)).unwrap();
+        info.textual_data[0].text = String::from("Ünïcödé");
+
+        // Latin-1 characters are written as single bytes and read back as they are.
+        let mut output = Vec::new();
+        Writer::write(&mut output, &info).unwrap();
+        assert!(output.windows(7).any(|w| w == b"\xdcn\xefc\xf6d\xe9"));
+        let parsed = Parser::parse(&mut Cursor::new(&output)).unwrap();
+        assert_eq!(parsed.textual_data[0].text, "Ünïcödé");
+
+        // Characters outside of Latin-1 cannot be written to tEXt chunks, which would need iTXt instead.
+        info.textual_data[0].keyword = String::from("Başlık");
+        assert!(matches!(
+            Writer::write(&mut Vec::new(), &info),
+            Err(WriterError::InvalidFieldValue)
+        ));
+    }
+}